@@ -0,0 +1,64 @@
+//! Mapping of numbers to the symbols of a user-supplied alphabet.
+
+use std::convert::TryFrom;
+
+#[derive(Debug, Clone)]
+pub struct Alphabet {
+    symbols: Vec<String>,
+}
+impl Alphabet {
+    /// Parses an alphabet of characters, where `a-z` is expanded to all characters from
+    /// `a` to `z` (inclusive). A `-` at the start or end is taken literally.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let chars: Vec<char> = spec.chars().collect();
+        let mut symbols = Vec::with_capacity(chars.len());
+        let mut pos = 0;
+        while pos < chars.len() {
+            let c = chars[pos];
+            if chars.get(pos + 1) == Some(&'-') && pos + 2 < chars.len() {
+                let end = chars[pos + 2];
+                if end < c {
+                    return Err(format!(
+                        "The range '{}-{}' in the alphabet is backwards.",
+                        c, end
+                    ));
+                }
+                symbols.extend((c..=end).map(String::from));
+                pos += 3;
+            } else {
+                symbols.push(c.to_string());
+                pos += 1;
+            }
+        }
+        Self::new(symbols)
+    }
+    /// Takes every line of `text` as a symbol. Empty lines are ignored.
+    pub fn from_lines(text: &str) -> Result<Self, String> {
+        Self::new(
+            text.lines()
+                .filter(|line| !line.is_empty())
+                .map(str::to_owned)
+                .collect(),
+        )
+    }
+    fn new(symbols: Vec<String>) -> Result<Self, String> {
+        if symbols.is_empty() {
+            Err("The alphabet is empty.".to_owned())
+        } else {
+            Ok(Self { symbols })
+        }
+    }
+    pub fn get(&self, index: u32) -> Result<&str, String> {
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| self.symbols.get(index))
+            .map(String::as_str)
+            .ok_or_else(|| {
+                format!(
+                    "Index {} is outside the alphabet of {} symbols.",
+                    index,
+                    self.symbols.len()
+                )
+            })
+    }
+}
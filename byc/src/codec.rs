@@ -0,0 +1,512 @@
+//! Streaming base-N codecs.
+
+use std::str::FromStr;
+
+/// A streaming conversion of bytes.
+///
+/// The errors are messages which include the byte offset of the failure.
+pub trait Transcoder {
+    /// Processes `input`, which starts at byte `offset` of the stream, appending to `output`.
+    fn update(&mut self, input: &[u8], offset: u64, output: &mut Vec<u8>) -> Result<(), String>;
+    /// Called after the last input, which ended at byte `offset`.
+    fn finish(&mut self, offset: u64, output: &mut Vec<u8>) -> Result<(), String>;
+}
+
+/// Runs the output of each [`Transcoder`] through the next.
+#[derive(Default)]
+pub struct Chain {
+    stages: Vec<Box<dyn Transcoder>>,
+    /// The offset in the output of each stage, used as the offset of the next stage's input.
+    offsets: Vec<u64>,
+}
+impl Chain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn push(&mut self, stage: Box<dyn Transcoder>) {
+        self.stages.push(stage);
+        self.offsets.push(0);
+    }
+    fn run(
+        &mut self,
+        input: &[u8],
+        offset: u64,
+        output: &mut Vec<u8>,
+        finish: bool,
+    ) -> Result<(), String> {
+        let mut input = input.to_vec();
+        let mut offset = offset;
+        for (stage, stage_offset) in self.stages.iter_mut().zip(self.offsets.iter_mut()) {
+            let mut out = Vec::with_capacity(input.len() * 2);
+            if !input.is_empty() {
+                stage.update(&input, offset, &mut out)?;
+            }
+            if finish {
+                stage.finish(offset + input.len() as u64, &mut out)?;
+            }
+            offset = *stage_offset;
+            *stage_offset += out.len() as u64;
+            input = out;
+        }
+        output.extend_from_slice(&input);
+        Ok(())
+    }
+}
+impl Transcoder for Chain {
+    fn update(&mut self, input: &[u8], offset: u64, output: &mut Vec<u8>) -> Result<(), String> {
+        self.run(input, offset, output, false)
+    }
+    fn finish(&mut self, offset: u64, output: &mut Vec<u8>) -> Result<(), String> {
+        self.run(&[], offset, output, true)
+    }
+}
+
+/// Inserts a newline after every `width` bytes.
+pub struct Wrap {
+    width: usize,
+    column: usize,
+}
+impl Wrap {
+    pub fn new(width: usize) -> Self {
+        assert!(width > 0);
+        Self { width, column: 0 }
+    }
+}
+impl Transcoder for Wrap {
+    fn update(&mut self, input: &[u8], _: u64, output: &mut Vec<u8>) -> Result<(), String> {
+        for byte in input {
+            if self.column == self.width {
+                output.push(b'\n');
+                self.column = 0;
+            }
+            output.push(*byte);
+            self.column += 1;
+        }
+        Ok(())
+    }
+    fn finish(&mut self, _: u64, _: &mut Vec<u8>) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    Base64,
+    Base64Url,
+    Base32,
+    Base58,
+    Hex,
+    Ascii85,
+}
+impl FromStr for Codec {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "base64" | "b64" => Ok(Self::Base64),
+            "base64url" | "base64-url" | "b64url" => Ok(Self::Base64Url),
+            "base32" | "b32" => Ok(Self::Base32),
+            "base58" | "b58" => Ok(Self::Base58),
+            "hex" | "base16" => Ok(Self::Hex),
+            "ascii85" | "base85" | "a85" => Ok(Self::Ascii85),
+            _ => Err(format!(
+                "Codec '{}' is not valid. Available are: \
+                base64, base64url, base32, base58, hex and ascii85.",
+                s
+            )),
+        }
+    }
+}
+impl Codec {
+    pub fn encoder(&self) -> Box<dyn Transcoder> {
+        match self.bits() {
+            Some(bits) => Box::new(BitsEncoder::new(bits)),
+            None if *self == Self::Base58 => Box::new(Base58::encoder()),
+            None => Box::new(Ascii85Encoder::default()),
+        }
+    }
+    pub fn decoder(&self) -> Box<dyn Transcoder> {
+        match self.bits() {
+            Some(bits) => Box::new(BitsDecoder::new(bits)),
+            None if *self == Self::Base58 => Box::new(Base58::decoder()),
+            None => Box::new(Ascii85Decoder::default()),
+        }
+    }
+    fn bits(&self) -> Option<Bits> {
+        Some(match self {
+            Self::Base64 => Bits {
+                alphabet: b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/",
+                bits: 6,
+                group: 4,
+                padding: Padding::Required,
+                case_insensitive: false,
+            },
+            Self::Base64Url => Bits {
+                alphabet: b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_",
+                bits: 6,
+                group: 4,
+                padding: Padding::Optional,
+                case_insensitive: false,
+            },
+            Self::Base32 => Bits {
+                alphabet: b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567",
+                bits: 5,
+                group: 8,
+                padding: Padding::Required,
+                case_insensitive: true,
+            },
+            Self::Hex => Bits {
+                alphabet: b"0123456789abcdef",
+                bits: 4,
+                group: 2,
+                padding: Padding::None,
+                case_insensitive: true,
+            },
+            Self::Base58 | Self::Ascii85 => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Padding {
+    /// Written when encoding and required when decoding.
+    Required,
+    /// Not written when encoding, but accepted when decoding.
+    Optional,
+    None,
+}
+
+/// An encoding where every character represents `bits` bits, as specified by RFC 4648.
+#[derive(Debug, Clone, Copy)]
+struct Bits {
+    alphabet: &'static [u8],
+    bits: u32,
+    /// The number of characters in a padded group.
+    group: usize,
+    padding: Padding,
+    case_insensitive: bool,
+}
+impl Bits {
+    fn value(&self, c: u8) -> Option<u32> {
+        self.alphabet
+            .iter()
+            .position(|a| *a == c || (self.case_insensitive && a.eq_ignore_ascii_case(&c)))
+            .map(|pos| pos as u32)
+    }
+}
+
+struct BitsEncoder {
+    bits: Bits,
+    buffer: u32,
+    buffered: u32,
+    written: usize,
+}
+impl BitsEncoder {
+    fn new(bits: Bits) -> Self {
+        Self {
+            bits,
+            buffer: 0,
+            buffered: 0,
+            written: 0,
+        }
+    }
+}
+impl Transcoder for BitsEncoder {
+    fn update(&mut self, input: &[u8], _: u64, output: &mut Vec<u8>) -> Result<(), String> {
+        let bits = self.bits.bits;
+        let mask = (1 << bits) - 1;
+        for byte in input {
+            self.buffer = (self.buffer << 8) | *byte as u32;
+            self.buffered += 8;
+            while self.buffered >= bits {
+                self.buffered -= bits;
+                output.push(self.bits.alphabet[((self.buffer >> self.buffered) & mask) as usize]);
+                self.written += 1;
+            }
+        }
+        Ok(())
+    }
+    fn finish(&mut self, _: u64, output: &mut Vec<u8>) -> Result<(), String> {
+        let bits = self.bits.bits;
+        if self.buffered > 0 {
+            let mask = (1 << bits) - 1;
+            output.push(
+                self.bits.alphabet[((self.buffer << (bits - self.buffered)) & mask) as usize],
+            );
+            self.written += 1;
+            self.buffered = 0;
+        }
+        if self.bits.padding == Padding::Required {
+            while !self.written.is_multiple_of(self.bits.group) {
+                output.push(b'=');
+                self.written += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+struct BitsDecoder {
+    bits: Bits,
+    buffer: u32,
+    buffered: u32,
+    /// Data characters in the current group.
+    characters: usize,
+    padding: usize,
+}
+impl BitsDecoder {
+    fn new(bits: Bits) -> Self {
+        Self {
+            bits,
+            buffer: 0,
+            buffered: 0,
+            characters: 0,
+            padding: 0,
+        }
+    }
+    fn check_group(&self, offset: u64) -> Result<(), String> {
+        if self.buffered >= self.bits.bits {
+            return Err(format!("Incomplete group ending at byte {}.", offset));
+        }
+        let expected = (self.bits.group - self.characters) % self.bits.group;
+        let valid_padding = match self.bits.padding {
+            Padding::Required => self.padding == expected,
+            Padding::Optional => self.padding == 0 || self.padding == expected,
+            Padding::None => self.padding == 0,
+        };
+        if valid_padding {
+            Ok(())
+        } else {
+            Err(format!(
+                "Invalid padding ending at byte {}: expected {} '=', found {}.",
+                offset, expected, self.padding
+            ))
+        }
+    }
+}
+impl Transcoder for BitsDecoder {
+    fn update(&mut self, input: &[u8], offset: u64, output: &mut Vec<u8>) -> Result<(), String> {
+        for (pos, byte) in input.iter().enumerate() {
+            let offset = offset + pos as u64;
+            if byte.is_ascii_whitespace() {
+                continue;
+            }
+            if *byte == b'=' && self.bits.padding != Padding::None {
+                if self.padding == 0 && self.characters == 0 {
+                    return Err(format!("Unexpected padding at byte {}.", offset));
+                }
+                self.padding += 1;
+                continue;
+            }
+            if self.padding > 0 {
+                return Err(format!(
+                    "Unexpected '{}' after padding at byte {}.",
+                    *byte as char, offset
+                ));
+            }
+            let value = self.bits.value(*byte).ok_or_else(|| {
+                format!(
+                    "Invalid character '{}' at byte {}.",
+                    String::from_utf8_lossy(&[*byte]),
+                    offset
+                )
+            })?;
+            self.buffer = (self.buffer << self.bits.bits) | value;
+            self.buffered += self.bits.bits;
+            self.characters = (self.characters + 1) % self.bits.group;
+            if self.buffered >= 8 {
+                self.buffered -= 8;
+                output.push((self.buffer >> self.buffered) as u8);
+            }
+        }
+        Ok(())
+    }
+    fn finish(&mut self, offset: u64, _: &mut Vec<u8>) -> Result<(), String> {
+        self.check_group(offset)
+    }
+}
+
+/// Base58 has no fixed group size, so the whole input is buffered.
+struct Base58 {
+    input: Vec<u8>,
+    decode: bool,
+}
+impl Base58 {
+    const ALPHABET: &'static [u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+    fn encoder() -> Self {
+        Self {
+            input: Vec::new(),
+            decode: false,
+        }
+    }
+    fn decoder() -> Self {
+        Self {
+            input: Vec::new(),
+            decode: true,
+        }
+    }
+    /// Converts the big-endian number `digits` in `from` to `to`.
+    fn convert(digits: impl Iterator<Item = u32>, from: u32, to: u32) -> Vec<u8> {
+        // Little-endian digits in `to`.
+        let mut result: Vec<u8> = Vec::new();
+        for digit in digits {
+            let mut carry = digit;
+            for d in result.iter_mut() {
+                carry += *d as u32 * from;
+                *d = (carry % to) as u8;
+                carry /= to;
+            }
+            while carry > 0 {
+                result.push((carry % to) as u8);
+                carry /= to;
+            }
+        }
+        result.reverse();
+        result
+    }
+}
+impl Transcoder for Base58 {
+    fn update(&mut self, input: &[u8], offset: u64, _: &mut Vec<u8>) -> Result<(), String> {
+        if self.decode {
+            for (pos, byte) in input.iter().enumerate() {
+                if byte.is_ascii_whitespace() {
+                    continue;
+                }
+                let value = Self::ALPHABET
+                    .iter()
+                    .position(|a| a == byte)
+                    .ok_or_else(|| {
+                        format!(
+                            "Invalid character '{}' at byte {}.",
+                            String::from_utf8_lossy(&[*byte]),
+                            offset + pos as u64
+                        )
+                    })?;
+                self.input.push(value as u8);
+            }
+        } else {
+            self.input.extend_from_slice(input);
+        }
+        Ok(())
+    }
+    fn finish(&mut self, _: u64, output: &mut Vec<u8>) -> Result<(), String> {
+        let (from, to) = if self.decode { (58, 256) } else { (256, 58) };
+        // Leading zeroes are encoded as leading `1`s and vice versa.
+        let leading = self.input.iter().take_while(|b| **b == 0).count();
+        let digits = Self::convert(self.input[leading..].iter().map(|b| *b as u32), from, to);
+        if self.decode {
+            output.extend(std::iter::repeat_n(0, leading));
+            output.extend_from_slice(&digits);
+        } else {
+            output.extend(std::iter::repeat_n(Self::ALPHABET[0], leading));
+            output.extend(digits.iter().map(|d| Self::ALPHABET[*d as usize]));
+        }
+        self.input.clear();
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Ascii85Encoder {
+    group: [u8; 4],
+    len: usize,
+}
+impl Ascii85Encoder {
+    fn write_group(&self, output: &mut Vec<u8>) {
+        let value = u32::from_be_bytes(self.group);
+        if value == 0 && self.len == 4 {
+            output.push(b'z');
+            return;
+        }
+        let mut chars = [0; 5];
+        let mut value = value;
+        for c in chars.iter_mut().rev() {
+            *c = (value % 85) as u8 + b'!';
+            value /= 85;
+        }
+        output.extend_from_slice(&chars[..self.len + 1]);
+    }
+}
+impl Transcoder for Ascii85Encoder {
+    fn update(&mut self, input: &[u8], _: u64, output: &mut Vec<u8>) -> Result<(), String> {
+        for byte in input {
+            self.group[self.len] = *byte;
+            self.len += 1;
+            if self.len == 4 {
+                self.write_group(output);
+                self.len = 0;
+            }
+        }
+        Ok(())
+    }
+    fn finish(&mut self, _: u64, output: &mut Vec<u8>) -> Result<(), String> {
+        if self.len > 0 {
+            self.group[self.len..].iter_mut().for_each(|b| *b = 0);
+            self.write_group(output);
+            self.len = 0;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Ascii85Decoder {
+    group: [u8; 5],
+    len: usize,
+}
+impl Ascii85Decoder {
+    fn write_group(&self, offset: u64, output: &mut Vec<u8>) -> Result<(), String> {
+        let value = self
+            .group
+            .iter()
+            .try_fold(0_u32, |acc, c| acc.checked_mul(85)?.checked_add(*c as u32));
+        match value {
+            Some(value) => {
+                output.extend_from_slice(&value.to_be_bytes()[..self.len - 1]);
+                Ok(())
+            }
+            None => Err(format!("Group ending at byte {} is out of range.", offset)),
+        }
+    }
+}
+impl Transcoder for Ascii85Decoder {
+    fn update(&mut self, input: &[u8], offset: u64, output: &mut Vec<u8>) -> Result<(), String> {
+        for (pos, byte) in input.iter().enumerate() {
+            let offset = offset + pos as u64;
+            match byte {
+                b'z' if self.len == 0 => output.extend_from_slice(&[0; 4]),
+                b'z' => return Err(format!("Unexpected 'z' inside a group at byte {}.", offset)),
+                b'!'..=b'u' => {
+                    self.group[self.len] = byte - b'!';
+                    self.len += 1;
+                    if self.len == 5 {
+                        self.write_group(offset, output)?;
+                        self.len = 0;
+                    }
+                }
+                _ if byte.is_ascii_whitespace() => {}
+                _ => {
+                    return Err(format!(
+                        "Invalid character '{}' at byte {}.",
+                        String::from_utf8_lossy(&[*byte]),
+                        offset
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+    fn finish(&mut self, offset: u64, output: &mut Vec<u8>) -> Result<(), String> {
+        match self.len {
+            0 => Ok(()),
+            1 => Err(format!("Incomplete group ending at byte {}.", offset)),
+            _ => {
+                // Pad with the highest digit, `u`.
+                self.group[self.len..].iter_mut().for_each(|c| *c = 84);
+                let result = self.write_group(offset, output);
+                self.len = 0;
+                result
+            }
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Hexdumps in the plain format of `xxd`, and parsing them back into bytes.
+
+use std::fmt::Write;
+
+use crate::codec::Transcoder;
+
+/// Writes lines of `offset: hex  ascii`.
+pub struct Dump {
+    columns: usize,
+    /// Bytes per group of hex digits. 0 means no grouping.
+    group: usize,
+    offset: u64,
+    line: Vec<u8>,
+}
+impl Dump {
+    /// `columns` must be greater than 0.
+    pub fn new(columns: usize, group: usize) -> Self {
+        assert!(columns > 0);
+        Self {
+            columns,
+            group: if group == 0 { columns } else { group },
+            offset: 0,
+            line: Vec::with_capacity(columns),
+        }
+    }
+    fn write_line(&mut self, output: &mut Vec<u8>) {
+        let mut line = String::with_capacity(self.columns * 4 + 12);
+        // UNWRAP: writing to a String can't fail.
+        write!(line, "{:08x}:", self.offset).unwrap();
+        let hex_start = line.len();
+        for (pos, byte) in self.line.iter().enumerate() {
+            if pos % self.group == 0 {
+                line.push(' ');
+            }
+            write!(line, "{:02x}", byte).unwrap();
+        }
+        // Pad the hex of a short line so the text column lines up.
+        let groups = self.columns.div_ceil(self.group);
+        let width = hex_start + self.columns * 2 + groups;
+        while line.len() < width {
+            line.push(' ');
+        }
+        line.push_str("  ");
+        line.extend(self.line.iter().map(|b| match b {
+            0x20..=0x7e => *b as char,
+            _ => '.',
+        }));
+        line.push('\n');
+        output.extend_from_slice(line.as_bytes());
+        self.offset += self.line.len() as u64;
+        self.line.clear();
+    }
+}
+impl Transcoder for Dump {
+    fn update(&mut self, input: &[u8], _: u64, output: &mut Vec<u8>) -> Result<(), String> {
+        for byte in input {
+            self.line.push(*byte);
+            if self.line.len() == self.columns {
+                self.write_line(output);
+            }
+        }
+        Ok(())
+    }
+    fn finish(&mut self, _: u64, output: &mut Vec<u8>) -> Result<(), String> {
+        if !self.line.is_empty() {
+            self.write_line(output);
+        }
+        Ok(())
+    }
+}
+
+/// The largest gap between the offsets of lines, as it's filled in memory.
+const MAX_GAP: u64 = 16 * 1024 * 1024;
+
+/// Parses the output of [`Dump`] (or `xxd`) back into bytes.
+///
+/// The hex ends at the first two consecutive spaces, after which the text column is ignored.
+/// Gaps between the offsets of lines are filled with zeroes, up to [`MAX_GAP`] bytes.
+#[derive(Default)]
+pub struct Undump {
+    line: Vec<u8>,
+    /// The byte offset of the start of `line` in the input.
+    line_offset: u64,
+    line_number: usize,
+    /// The number of bytes written.
+    position: u64,
+}
+impl Undump {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    fn parse_line(&mut self, output: &mut Vec<u8>) -> Result<(), String> {
+        self.line_number += 1;
+        let (line_number, line_offset) = (self.line_number, self.line_offset);
+        let error = |message: &str, column: usize| {
+            format!(
+                "{} At line {}, byte {}.",
+                message,
+                line_number,
+                line_offset + column as u64
+            )
+        };
+        let line = self.line.strip_suffix(b"\r").unwrap_or(&self.line);
+        if line.iter().all(u8::is_ascii_whitespace) {
+            return Ok(());
+        }
+        let colon = line
+            .iter()
+            .position(|b| *b == b':')
+            .ok_or_else(|| error("Missing ':' after the offset.", 0))?;
+        let offset = std::str::from_utf8(&line[..colon])
+            .ok()
+            .and_then(|offset| u64::from_str_radix(offset.trim(), 16).ok())
+            .ok_or_else(|| error("Invalid offset.", 0))?;
+        if offset < self.position {
+            return Err(error(
+                "The offset is before the end of the previous line.",
+                0,
+            ));
+        }
+        if offset - self.position > MAX_GAP {
+            return Err(error(
+                &format!(
+                    "The offset is more than {} MiB after the end of the previous line.",
+                    MAX_GAP / 1024 / 1024
+                ),
+                0,
+            ));
+        }
+        output.extend(std::iter::repeat_n(0, (offset - self.position) as usize));
+        self.position = offset;
+
+        let hex_start = colon + 1;
+        let hex_end = line[hex_start..]
+            .windows(2)
+            // Skip the single space after the colon.
+            .skip(1)
+            .position(|w| w == b"  ")
+            .map_or(line.len(), |pos| hex_start + 1 + pos);
+        let mut high = None;
+        for (pos, c) in line[hex_start..hex_end].iter().enumerate() {
+            let column = hex_start + pos;
+            if *c == b' ' || *c == b'\t' {
+                if high.is_some() {
+                    return Err(error("Odd number of hex digits in a group.", column));
+                }
+                continue;
+            }
+            let digit = (*c as char)
+                .to_digit(16)
+                .ok_or_else(|| error(&format!("Invalid hex digit '{}'.", *c as char), column))?;
+            match high.take() {
+                Some(high) => {
+                    output.push((high << 4 | digit) as u8);
+                    self.position += 1;
+                }
+                None => high = Some(digit),
+            }
+        }
+        if high.is_some() {
+            return Err(error("Odd number of hex digits in a group.", hex_end));
+        }
+        Ok(())
+    }
+}
+impl Transcoder for Undump {
+    fn update(&mut self, input: &[u8], offset: u64, output: &mut Vec<u8>) -> Result<(), String> {
+        for (pos, byte) in input.iter().enumerate() {
+            if *byte == b'\n' {
+                self.parse_line(output)?;
+                self.line.clear();
+                self.line_offset = offset + pos as u64 + 1;
+            } else {
+                self.line.push(*byte);
+            }
+        }
+        Ok(())
+    }
+    fn finish(&mut self, _: u64, output: &mut Vec<u8>) -> Result<(), String> {
+        if !self.line.is_empty() {
+            self.parse_line(output)?;
+            self.line.clear();
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,179 @@
+//! Conversion between text and the numbers representing it.
+
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+/// The unit each number represents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    /// A Unicode scalar value, i.e. a [`char`].
+    Scalar,
+    /// A byte of the UTF-8 encoding.
+    Utf8,
+    /// A code unit of the UTF-16 encoding.
+    Utf16,
+}
+impl FromStr for Unit {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "scalar" | "char" | "codepoint" => Ok(Self::Scalar),
+            "utf8" | "utf-8" | "byte" => Ok(Self::Utf8),
+            "utf16" | "utf-16" => Ok(Self::Utf16),
+            _ => Err(format!(
+                "Unit '{}' is not valid. Available are: scalar, utf8, utf16.",
+                s
+            )),
+        }
+    }
+}
+
+/// Parses `token`, detecting the base from its prefix.
+///
+/// `0x`, `0o`, `0b`, `U+` and `\u{...}` are recognized (case insensitive).
+/// Tokens without a prefix are parsed in `base`.
+pub fn parse_auto(token: &str, base: u32) -> Option<u32> {
+    let (digits, base) = split_prefix(token, base)?;
+    u32::from_str_radix(digits, base).ok()
+}
+
+/// Returns the digits of `token` and their base, as detected by [`parse_auto`].
+pub fn split_prefix(token: &str, base: u32) -> Option<(&str, u32)> {
+    fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+        let start = s.get(..prefix.len())?;
+        if start.eq_ignore_ascii_case(prefix) {
+            Some(&s[prefix.len()..])
+        } else {
+            None
+        }
+    }
+    let (digits, base) = if let Some(digits) = strip_prefix_ignore_case(token, "0x") {
+        (digits, 16)
+    } else if let Some(digits) = strip_prefix_ignore_case(token, "0o") {
+        (digits, 8)
+    } else if let Some(digits) = strip_prefix_ignore_case(token, "0b") {
+        (digits, 2)
+    } else if let Some(digits) = strip_prefix_ignore_case(token, "u+") {
+        (digits, 16)
+    } else if let Some(digits) = strip_prefix_ignore_case(token, "\\u{") {
+        (digits.strip_suffix('}')?, 16)
+    } else {
+        (token, base)
+    };
+    // `from_str_radix` accepts a leading `+`, which would make `0x+41` valid.
+    if digits.starts_with('+') {
+        return None;
+    }
+    Some((digits, base))
+}
+
+/// Formats `number` in `base`, which has to be in the range 2-36.
+/// Letters are lowercase.
+pub fn format_radix(mut number: u128, base: u32) -> String {
+    debug_assert!((2..=36).contains(&base));
+    if number == 0 {
+        return String::from("0");
+    }
+    let mut digits = Vec::with_capacity(32);
+    while number > 0 {
+        // UNWRAP: the remainder is always less than `base`.
+        digits.push(char::from_digit((number % base as u128) as u32, base).unwrap());
+        number /= base as u128;
+    }
+    digits.iter().rev().collect()
+}
+
+/// Calls `f` with every number of `c` in the `unit`.
+pub fn encode(c: char, unit: Unit, mut f: impl FnMut(u32)) {
+    match unit {
+        Unit::Scalar => f(c as u32),
+        Unit::Utf8 => c.encode_utf8(&mut [0; 4]).bytes().for_each(|b| f(b as u32)),
+        Unit::Utf16 => c
+            .encode_utf16(&mut [0; 2])
+            .iter()
+            .for_each(|u| f(*u as u32)),
+    }
+}
+
+/// Assembles text from numbers in the `unit`, one number at a time.
+///
+/// The errors are messages describing why the numbers don't form valid text.
+#[derive(Debug)]
+pub struct Decoder {
+    unit: Unit,
+    pending: [u8; 4],
+    pending_len: usize,
+    high_surrogate: Option<u16>,
+}
+impl Decoder {
+    pub fn new(unit: Unit) -> Self {
+        Self {
+            unit,
+            pending: [0; 4],
+            pending_len: 0,
+            high_surrogate: None,
+        }
+    }
+    /// Returns the character if `number` completed one.
+    pub fn push(&mut self, number: u32) -> Result<Option<char>, String> {
+        match self.unit {
+            Unit::Scalar => char::from_u32(number)
+                .map(Some)
+                .ok_or_else(|| format!("Failed to convert '{}' to a character.", number)),
+            Unit::Utf8 => {
+                let byte = u8::try_from(number)
+                    .map_err(|_| format!("'{}' is too large to be a UTF-8 byte.", number))?;
+                self.pending[self.pending_len] = byte;
+                self.pending_len += 1;
+                let expected = match self.pending[0] {
+                    0x00..=0x7f => 1,
+                    0xc0..=0xdf => 2,
+                    0xe0..=0xef => 3,
+                    0xf0..=0xf7 => 4,
+                    _ => 0,
+                };
+                if expected == 0 || (self.pending_len > 1 && byte & 0xc0 != 0x80) {
+                    self.pending_len = 0;
+                    return Err(format!("Byte '{}' is not valid UTF-8 here.", byte));
+                }
+                if self.pending_len < expected {
+                    return Ok(None);
+                }
+                let bytes = &self.pending[..self.pending_len];
+                self.pending_len = 0;
+                match std::str::from_utf8(bytes) {
+                    Ok(s) => Ok(s.chars().next()),
+                    Err(_) => Err(format!("The bytes {:?} are not valid UTF-8.", bytes)),
+                }
+            }
+            Unit::Utf16 => {
+                let unit = u16::try_from(number)
+                    .map_err(|_| format!("'{}' is too large to be a UTF-16 code unit.", number))?;
+                let is_high = (0xd800..0xdc00).contains(&unit);
+                let is_low = (0xdc00..0xe000).contains(&unit);
+                match self.high_surrogate.take() {
+                    Some(high) if is_low => {
+                        let c = 0x10000 + ((high as u32 - 0xd800) << 10) + (unit as u32 - 0xdc00);
+                        Ok(char::from_u32(c))
+                    }
+                    Some(high) => Err(format!("Unpaired surrogate '{}' in UTF-16.", high)),
+                    None if is_high => {
+                        self.high_surrogate = Some(unit);
+                        Ok(None)
+                    }
+                    None if is_low => Err(format!("Unpaired surrogate '{}' in UTF-16.", unit)),
+                    None => Ok(char::from_u32(unit as u32)),
+                }
+            }
+        }
+    }
+    /// Call when no more numbers will be pushed.
+    /// Returns an error if a character is incomplete.
+    pub fn finish(&self) -> Result<(), String> {
+        if self.pending_len > 0 || self.high_surrogate.is_some() {
+            Err("The input ended in the middle of a character.".to_owned())
+        } else {
+            Ok(())
+        }
+    }
+}
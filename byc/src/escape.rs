@@ -0,0 +1,480 @@
+//! Escaping of text into the string literals of languages, and back.
+
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::names;
+use crate::url::Component;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    C,
+    Json,
+    Shell,
+    Python,
+    Html,
+    Url,
+}
+impl FromStr for Language {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "rust" | "rs" => Ok(Self::Rust),
+            "c" => Ok(Self::C),
+            "json" | "js" => Ok(Self::Json),
+            "shell" | "sh" => Ok(Self::Shell),
+            "python" | "py" => Ok(Self::Python),
+            "html" | "xml" => Ok(Self::Html),
+            "url" | "percent" => Ok(Self::Url),
+            _ => Err(format!(
+                "Language '{}' is not valid. Available are: \
+                rust, c, json, shell, python, html and url.",
+                s
+            )),
+        }
+    }
+}
+impl Language {
+    /// The quote around the literal. Empty if the language doesn't use one.
+    pub fn quote(&self) -> &'static str {
+        match self {
+            Self::Rust | Self::C | Self::Json | Self::Python => "\"",
+            Self::Shell => "'",
+            Self::Html | Self::Url => "",
+        }
+    }
+
+    /// Appends `c`, escaped if needed, to `output`.
+    pub fn escape(&self, c: char, output: &mut String) {
+        // UNWRAP (all `write!`s): writing to a String can't fail.
+        match self {
+            Self::Rust => match c {
+                '\\' => output.push_str("\\\\"),
+                '"' => output.push_str("\\\""),
+                '\n' => output.push_str("\\n"),
+                '\r' => output.push_str("\\r"),
+                '\t' => output.push_str("\\t"),
+                '\0' => output.push_str("\\0"),
+                _ if c.is_control() => write!(output, "\\u{{{:x}}}", c as u32).unwrap(),
+                _ => output.push(c),
+            },
+            Self::C => match c {
+                '\\' => output.push_str("\\\\"),
+                '"' => output.push_str("\\\""),
+                '\n' => output.push_str("\\n"),
+                '\r' => output.push_str("\\r"),
+                '\t' => output.push_str("\\t"),
+                // Octal escapes always have 3 digits, unlike `\x`, which would
+                // swallow any following hex digits.
+                _ if c.is_control() || !c.is_ascii() => {
+                    for byte in c.encode_utf8(&mut [0; 4]).bytes() {
+                        write!(output, "\\{:03o}", byte).unwrap();
+                    }
+                }
+                _ => output.push(c),
+            },
+            Self::Json => match c {
+                '\\' => output.push_str("\\\\"),
+                '"' => output.push_str("\\\""),
+                '\n' => output.push_str("\\n"),
+                '\r' => output.push_str("\\r"),
+                '\t' => output.push_str("\\t"),
+                '\u{8}' => output.push_str("\\b"),
+                '\u{c}' => output.push_str("\\f"),
+                // The line and paragraph separators aren't allowed in JavaScript strings.
+                _ if c < ' ' || c == '\u{2028}' || c == '\u{2029}' => {
+                    write!(output, "\\u{:04x}", c as u32).unwrap()
+                }
+                _ => output.push(c),
+            },
+            Self::Python => match c {
+                '\\' => output.push_str("\\\\"),
+                '"' => output.push_str("\\\""),
+                '\n' => output.push_str("\\n"),
+                '\r' => output.push_str("\\r"),
+                '\t' => output.push_str("\\t"),
+                _ if c.is_control() && (c as u32) < 0x100 => {
+                    write!(output, "\\x{:02x}", c as u32).unwrap()
+                }
+                _ if c.is_control() => write!(output, "\\u{:04x}", c as u32).unwrap(),
+                _ => output.push(c),
+            },
+            Self::Shell => match c {
+                '\'' => output.push_str("'\\''"),
+                _ => output.push(c),
+            },
+            Self::Html => match c {
+                '&' => output.push_str("&amp;"),
+                '<' => output.push_str("&lt;"),
+                '>' => output.push_str("&gt;"),
+                '"' => output.push_str("&quot;"),
+                '\'' => output.push_str("&#39;"),
+                _ => output.push(c),
+            },
+            // The same as `--url-encode` of a component.
+            Self::Url => {
+                for byte in c.encode_utf8(&mut [0; 4]).bytes() {
+                    if Component::Any.keeps(byte) {
+                        output.push(byte as char);
+                    } else {
+                        write!(output, "%{:02X}", byte).unwrap();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Resolves the escapes of `literal`. Quoted literals and shell words may be surrounded
+    /// by whitespace, while it's part of the text of HTML and URLs, which have no quotes.
+    ///
+    /// The result may not be valid UTF-8, as C and URLs can escape arbitrary bytes.
+    /// The errors include the byte offset in `literal`.
+    pub fn unescape(&self, literal: &str) -> Result<Vec<u8>, String> {
+        let (trimmed, start) = if self.quote().is_empty() && *self != Self::Shell {
+            (literal, 0)
+        } else {
+            (literal.trim(), literal.len() - literal.trim_start().len())
+        };
+        if *self == Self::Shell {
+            return unescape_shell(trimmed, start);
+        }
+        let (inner, start) = match self.quote() {
+            "" => (trimmed, start),
+            quote => {
+                // Python also allows single quotes.
+                let quote = if *self == Self::Python && trimmed.starts_with('\'') {
+                    "'"
+                } else {
+                    quote
+                };
+                if trimmed.len() < 2 || !trimmed.starts_with(quote) || !trimmed.ends_with(quote) {
+                    return Err(format!(
+                        "Expected a literal surrounded by {} at byte {}.",
+                        quote, start
+                    ));
+                }
+                (&trimmed[1..trimmed.len() - 1], start + 1)
+            }
+        };
+        match self {
+            Self::Rust => common::parse_escaped_at(inner)
+                .map(|s| s.into_owned().into_bytes())
+                .map_err(|(pos, err)| format!("At byte {}: {}.", start + pos, err)),
+            Self::C => unescape_c(inner, start),
+            Self::Json => unescape_json(inner, start),
+            Self::Python => unescape_python(inner, start),
+            Self::Html => unescape_html(inner, start),
+            Self::Url => unescape_url(inner, start),
+            Self::Shell => unreachable!("handled above"),
+        }
+    }
+}
+
+fn invalid_escape(offset: usize) -> String {
+    format!("Invalid escape at byte {}.", offset)
+}
+
+/// Parses up to `max` hex digits at the start of `s`, requiring at least `min`.
+/// Returns the value and the number of digits.
+fn hex_digits(s: &str, min: usize, max: usize) -> Option<(u32, usize)> {
+    let len = s
+        .bytes()
+        .take(max)
+        .take_while(u8::is_ascii_hexdigit)
+        .count();
+    if len < min {
+        return None;
+    }
+    u32::from_str_radix(&s[..len], 16).ok().map(|v| (v, len))
+}
+
+fn push_char(output: &mut Vec<u8>, value: u32, offset: usize) -> Result<(), String> {
+    let c = char::from_u32(value)
+        .ok_or_else(|| format!("Invalid character {:#x} at byte {}.", value, offset))?;
+    output.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+    Ok(())
+}
+
+/// Calls `f` for every `\`, with the rest after the backslash and the output.
+/// `f` returns the number of bytes it consumed of the rest.
+fn unescape_backslashes(
+    s: &str,
+    start: usize,
+    mut f: impl FnMut(&str, usize, &mut Vec<u8>) -> Result<usize, String>,
+) -> Result<Vec<u8>, String> {
+    let mut output = Vec::with_capacity(s.len());
+    let mut pos = 0;
+    while let Some(next) = s[pos..].find('\\') {
+        output.extend_from_slice(&s.as_bytes()[pos..pos + next]);
+        let escape = pos + next;
+        let rest = &s[escape + 1..];
+        if rest.is_empty() {
+            return Err(invalid_escape(start + escape));
+        }
+        let consumed = f(rest, start + escape, &mut output)?;
+        pos = escape + 1 + consumed;
+    }
+    output.extend_from_slice(&s.as_bytes()[pos..]);
+    Ok(output)
+}
+
+fn unescape_c(s: &str, start: usize) -> Result<Vec<u8>, String> {
+    unescape_backslashes(s, start, |rest, offset, output| {
+        let simple = match rest.as_bytes()[0] {
+            b'n' => Some(b'\n'),
+            b't' => Some(b'\t'),
+            b'r' => Some(b'\r'),
+            b'a' => Some(7),
+            b'b' => Some(8),
+            b'f' => Some(12),
+            b'v' => Some(11),
+            b'\\' | b'"' | b'\'' | b'?' => Some(rest.as_bytes()[0]),
+            _ => None,
+        };
+        if let Some(byte) = simple {
+            output.push(byte);
+            return Ok(1);
+        }
+        match rest.as_bytes()[0] {
+            b'0'..=b'7' => {
+                let len = rest
+                    .bytes()
+                    .take(3)
+                    .take_while(|b| (b'0'..=b'7').contains(b))
+                    .count();
+                // UNWRAP: the digits are octal.
+                let value = u32::from_str_radix(&rest[..len], 8).unwrap();
+                let byte = u8::try_from(value)
+                    .map_err(|_| format!("Octal escape out of range at byte {}.", offset))?;
+                output.push(byte);
+                Ok(len)
+            }
+            b'x' => {
+                let (value, len) =
+                    hex_digits(&rest[1..], 1, usize::MAX).ok_or_else(|| invalid_escape(offset))?;
+                let byte = u8::try_from(value)
+                    .map_err(|_| format!("Hex escape out of range at byte {}.", offset))?;
+                output.push(byte);
+                Ok(1 + len)
+            }
+            b'u' | b'U' => {
+                let digits = if rest.starts_with('u') { 4 } else { 8 };
+                let (value, len) =
+                    hex_digits(&rest[1..], digits, digits).ok_or_else(|| invalid_escape(offset))?;
+                push_char(output, value, offset)?;
+                Ok(1 + len)
+            }
+            _ => Err(invalid_escape(offset)),
+        }
+    })
+}
+
+fn unescape_json(s: &str, start: usize) -> Result<Vec<u8>, String> {
+    unescape_backslashes(s, start, |rest, offset, output| {
+        let byte = match rest.as_bytes()[0] {
+            b'"' => b'"',
+            b'\\' => b'\\',
+            b'/' => b'/',
+            b'b' => 8,
+            b'f' => 12,
+            b'n' => b'\n',
+            b'r' => b'\r',
+            b't' => b'\t',
+            b'u' => {
+                let (value, _) =
+                    hex_digits(&rest[1..], 4, 4).ok_or_else(|| invalid_escape(offset))?;
+                if !(0xd800..0xdc00).contains(&value) {
+                    push_char(output, value, offset)?;
+                    return Ok(5);
+                }
+                // A surrogate pair, which has to be followed by the low surrogate.
+                let low = rest
+                    .get(5..)
+                    .and_then(|rest| rest.strip_prefix("\\u"))
+                    .and_then(|rest| hex_digits(rest, 4, 4))
+                    .map(|(low, _)| low)
+                    .filter(|low| (0xdc00..0xe000).contains(low))
+                    .ok_or_else(|| format!("Unpaired surrogate at byte {}.", offset))?;
+                push_char(
+                    output,
+                    0x10000 + ((value - 0xd800) << 10) + (low - 0xdc00),
+                    offset,
+                )?;
+                return Ok(11);
+            }
+            _ => return Err(invalid_escape(offset)),
+        };
+        output.push(byte);
+        Ok(1)
+    })
+}
+
+fn unescape_python(s: &str, start: usize) -> Result<Vec<u8>, String> {
+    unescape_backslashes(s, start, |rest, offset, output| {
+        let simple = match rest.as_bytes()[0] {
+            b'\\' | b'\'' | b'"' => Some(rest.as_bytes()[0]),
+            b'a' => Some(7),
+            b'b' => Some(8),
+            b'f' => Some(12),
+            b'n' => Some(b'\n'),
+            b'r' => Some(b'\r'),
+            b't' => Some(b'\t'),
+            b'v' => Some(11),
+            _ => None,
+        };
+        if let Some(byte) = simple {
+            output.push(byte);
+            return Ok(1);
+        }
+        match rest.as_bytes()[0] {
+            // Line continuation.
+            b'\n' => Ok(1),
+            b'0'..=b'7' => {
+                let len = rest
+                    .bytes()
+                    .take(3)
+                    .take_while(|b| (b'0'..=b'7').contains(b))
+                    .count();
+                // UNWRAP: the digits are octal.
+                push_char(
+                    output,
+                    u32::from_str_radix(&rest[..len], 8).unwrap(),
+                    offset,
+                )?;
+                Ok(len)
+            }
+            b'x' | b'u' | b'U' => {
+                let digits = match rest.as_bytes()[0] {
+                    b'x' => 2,
+                    b'u' => 4,
+                    _ => 8,
+                };
+                let (value, len) =
+                    hex_digits(&rest[1..], digits, digits).ok_or_else(|| invalid_escape(offset))?;
+                push_char(output, value, offset)?;
+                Ok(1 + len)
+            }
+            b'N' => {
+                let end = rest
+                    .find('}')
+                    .filter(|_| rest[1..].starts_with('{'))
+                    .ok_or_else(|| invalid_escape(offset))?;
+                let name = &rest[2..end];
+                let c = unicode_names2::character(name).ok_or_else(|| {
+                    format!("Unknown character name '{}' at byte {}.", name, offset)
+                })?;
+                push_char(output, c as u32, offset)?;
+                Ok(end + 1)
+            }
+            // Python keeps unknown escapes as they are.
+            _ => {
+                output.push(b'\\');
+                Ok(0)
+            }
+        }
+    })
+}
+
+fn unescape_shell(s: &str, start: usize) -> Result<Vec<u8>, String> {
+    let mut output = Vec::with_capacity(s.len());
+    let mut chars = s.char_indices();
+    while let Some((pos, c)) = chars.next() {
+        let offset = start + pos;
+        match c {
+            '\'' => loop {
+                match chars.next() {
+                    Some((_, '\'')) => break,
+                    Some((_, c)) => output.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+                    None => return Err(format!("Unterminated ' starting at byte {}.", offset)),
+                }
+            },
+            '"' => loop {
+                match chars.next() {
+                    Some((_, '"')) => break,
+                    Some((pos, '\\')) => match chars.next() {
+                        Some((_, '\n')) => {}
+                        Some((_, c @ '$')) | Some((_, c @ '`')) | Some((_, c @ '"'))
+                        | Some((_, c @ '\\')) => output.push(c as u8),
+                        Some((_, c)) => {
+                            output.push(b'\\');
+                            output.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes())
+                        }
+                        None => return Err(invalid_escape(start + pos)),
+                    },
+                    Some((_, c)) => output.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+                    None => return Err(format!("Unterminated \" starting at byte {}.", offset)),
+                }
+            },
+            '\\' => match chars.next() {
+                Some((_, '\n')) => {}
+                Some((_, c)) => output.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+                None => return Err(invalid_escape(offset)),
+            },
+            _ if c.is_whitespace() => {
+                return Err(format!(
+                    "Unquoted whitespace at byte {}. The input must be a single word.",
+                    offset
+                ))
+            }
+            _ => output.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+        }
+    }
+    Ok(output)
+}
+
+fn unescape_html(s: &str, start: usize) -> Result<Vec<u8>, String> {
+    let mut output = Vec::with_capacity(s.len());
+    let mut pos = 0;
+    while let Some(next) = s[pos..].find('&') {
+        output.extend_from_slice(&s.as_bytes()[pos..pos + next]);
+        let amp = pos + next;
+        let rest = &s[amp + 1..];
+        let end = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '#'))
+            .filter(|end| *end > 0 && rest[*end..].starts_with(';'));
+        let end = match end {
+            Some(end) => end,
+            None => {
+                // Not an entity, so a literal `&`.
+                output.push(b'&');
+                pos = amp + 1;
+                continue;
+            }
+        };
+        let entity = format!("&{};", &rest[..end]);
+        let characters = if entity.starts_with("&#") {
+            names::lookup(&entity).map_err(|err| format!("{} At byte {}.", err, start + amp))?
+        } else {
+            names::entity(&entity)
+                .map(Cow::Borrowed)
+                .ok_or_else(|| format!("Unknown entity '{}' at byte {}.", entity, start + amp))?
+        };
+        output.extend_from_slice(characters.as_bytes());
+        pos = amp + 1 + end + 1;
+    }
+    output.extend_from_slice(&s.as_bytes()[pos..]);
+    Ok(output)
+}
+
+fn unescape_url(s: &str, start: usize) -> Result<Vec<u8>, String> {
+    let mut output = Vec::with_capacity(s.len());
+    let bytes = s.as_bytes();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        if bytes[pos] == b'%' {
+            let value = s
+                .get(pos + 1..pos + 3)
+                .and_then(|digits| hex_digits(digits, 2, 2))
+                .ok_or_else(|| format!("Invalid percent-encoding at byte {}.", start + pos))?;
+            output.push(value.0 as u8);
+            pos += 3;
+        } else {
+            output.push(bytes[pos]);
+            pos += 1;
+        }
+    }
+    Ok(output)
+}
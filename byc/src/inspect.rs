@@ -0,0 +1,122 @@
+//! Listing of the Unicode properties of each character.
+//!
+//! The properties come from the tables compiled into the Unicode crates it depends on,
+//! so no data is needed at runtime.
+
+use icu_properties::{props::EastAsianWidth, CodePointMapData};
+use unicode_general_category::{get_general_category, GeneralCategory};
+use unicode_script::UnicodeScript;
+
+/// The header of the columns of [`inspect`].
+pub const HEADER: &str =
+    "OFFSET\tCODE POINT\tCHAR\tUTF-8\tNAME\tCATEGORY\tSCRIPT\tEA WIDTH\tWARNINGS";
+
+/// Describes `c`, which is at byte `offset` of the input, as a line of tab separated columns.
+pub fn inspect(c: char, offset: u64) -> String {
+    let category = get_general_category(c);
+    let utf8 = c
+        .encode_utf8(&mut [0; 4])
+        .bytes()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let name = unicode_names2::name(c)
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| match category {
+            GeneralCategory::Control => "<control>".to_owned(),
+            GeneralCategory::PrivateUse => "<private use>".to_owned(),
+            GeneralCategory::Unassigned => "<unassigned>".to_owned(),
+            _ => "<unknown>".to_owned(),
+        });
+    let warnings = warnings(c, category);
+    format!(
+        "{}\tU+{:04X}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+        offset,
+        c as u32,
+        display(c, category),
+        utf8,
+        name,
+        category.abbreviation(),
+        c.script().full_name(),
+        east_asian_width(c),
+        if warnings.is_empty() {
+            "-".to_owned()
+        } else {
+            warnings.join(", ")
+        }
+    )
+}
+
+/// Escapes characters which would be invisible or break the layout of the output.
+fn display(c: char, category: GeneralCategory) -> String {
+    match category {
+        _ if c == ' ' => c.to_string(),
+        GeneralCategory::Control
+        | GeneralCategory::Format
+        | GeneralCategory::SpaceSeparator
+        | GeneralCategory::LineSeparator
+        | GeneralCategory::ParagraphSeparator
+        | GeneralCategory::NonspacingMark
+        | GeneralCategory::EnclosingMark
+        | GeneralCategory::Unassigned
+        | GeneralCategory::PrivateUse
+        | GeneralCategory::Surrogate => c.escape_unicode().to_string(),
+        _ => c.to_string(),
+    }
+}
+
+/// The short name of the East_Asian_Width property (UAX #11).
+fn east_asian_width(c: char) -> &'static str {
+    match CodePointMapData::<EastAsianWidth>::new().get(c) {
+        EastAsianWidth::Fullwidth => "F",
+        EastAsianWidth::Halfwidth => "H",
+        EastAsianWidth::Wide => "W",
+        EastAsianWidth::Narrow => "Na",
+        EastAsianWidth::Ambiguous => "A",
+        _ => "N",
+    }
+}
+
+fn warnings(c: char, category: GeneralCategory) -> Vec<String> {
+    let mut warnings = Vec::new();
+    match c {
+        '\u{200b}' => warnings.push("zero width space".to_owned()),
+        '\u{200c}' => warnings.push("zero width non-joiner".to_owned()),
+        '\u{200d}' => warnings.push("zero width joiner".to_owned()),
+        '\u{2060}' => warnings.push("word joiner".to_owned()),
+        '\u{feff}' => warnings.push("byte order mark".to_owned()),
+        '\u{00ad}' => warnings.push("soft hyphen".to_owned()),
+        '\u{00a0}' | '\u{202f}' => warnings.push("non-breaking space".to_owned()),
+        '\u{202a}'..='\u{202e}'
+        | '\u{2066}'..='\u{2069}'
+        | '\u{200e}'
+        | '\u{200f}'
+        | '\u{061c}' => warnings.push("bidi control".to_owned()),
+        _ => {}
+    }
+    if warnings.is_empty() {
+        match category {
+            GeneralCategory::Format => warnings.push("invisible".to_owned()),
+            GeneralCategory::SpaceSeparator if c != ' ' => {
+                warnings.push("unusual space".to_owned())
+            }
+            GeneralCategory::LineSeparator | GeneralCategory::ParagraphSeparator => {
+                warnings.push("unusual line break".to_owned())
+            }
+            GeneralCategory::Control if !matches!(c, '\n' | '\r' | '\t') => {
+                warnings.push("control".to_owned())
+            }
+            GeneralCategory::PrivateUse => warnings.push("private use".to_owned()),
+            GeneralCategory::Unassigned => warnings.push("unassigned".to_owned()),
+            _ => {}
+        }
+    }
+    if !c.is_ascii() {
+        let mut buf = [0; 4];
+        let skeleton: String = unicode_security::skeleton(c.encode_utf8(&mut buf)).collect();
+        if skeleton != c.to_string() && skeleton.is_ascii() && !skeleton.trim().is_empty() {
+            warnings.push(format!("confusable with '{}'", skeleton));
+        }
+    }
+    warnings
+}
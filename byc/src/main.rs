@@ -2,14 +2,82 @@
 
 use common::ExitDisplay;
 use getopts::Options;
-use std::{borrow::Cow, char, env, io, io::prelude::*};
+use std::{borrow::Cow, convert::TryFrom, env, io, io::prelude::*};
+
+mod alphabet;
+mod codec;
+mod dump;
+mod encode;
+mod escape;
+mod inspect;
+mod names;
+mod normalize;
+mod output;
+mod pack;
+mod stream;
+mod transform;
+mod url;
+
+use alphabet::Alphabet;
+use codec::{Chain, Codec, Transcoder};
+use encode::{Decoder, Unit};
+use escape::Language;
+use normalize::{Form, Normalizer};
+use output::Output;
+use pack::Layout;
+use stream::{FlushingStdin, Tokens};
+use transform::Step;
+use url::Component;
 
 pub fn print_usage(program: &str, opts: Options) -> ! {
     let brief = format!(
         "Usage: {prog} RANGE... [options]\n\n\
 Will read input from stdin \
 (often piped from another program, such as ran using the ascii range) \
-and convert numbers to characters according to UTF-8.\n",
+and convert numbers to characters according to UTF-8.\n\
+With --encode, the text from stdin is instead converted to numbers.\n\
+Use --no-newline when decoding to get the exact text back.\n\
+\n\
+With --codec, stdin is encoded (or decoded with --decode) using \
+base64, base64url, base32, base58, hex or ascii85.\n\
+\n\
+With --transform, stdin is run through a comma-separated list of steps: \
+rot13, rot47, caesar:N, atbash, xor:KEY (0x followed by hex, or text), reverse, \
+a codec to encode with or decode:CODEC. For example --transform xor:0x42,base64\n\
+\n\
+With --inspect, every character of stdin is listed with its code point, UTF-8 bytes, \
+name, general category, script and East Asian width. \
+Invisible and confusable characters are flagged.\n\
+\n\
+With --alphabet or --alphabet-file, every number is an index into the given symbols. \
+In --alphabet, ranges like a-z are expanded. \
+--alphabet-file takes one symbol (which may be a word) per line.\n\
+\n\
+With --names, tokens which aren't numbers are looked up as Unicode names \
+(LATIN SMALL LETTER A WITH ACUTE, optionally as \\N{{...}}), HTML entities (&eacute;) \
+or code points (U+1F600), ignoring case.\n\
+\n\
+With --pack, every number is written as a fixed-width binary value, \
+such as u16le or f64be. --unpack reads such values and writes them as numbers. \
+The base flags apply to the numbers.\n\
+\n\
+With --dump, stdin is written as a hexdump in the format of xxd. \
+--undump parses such a hexdump back into bytes.\n\
+\n\
+With --escape, stdin is written as a string literal of \
+rust, c, json, shell, python, html or url. \
+--unescape reads such a literal and writes the text it contains.\n\
+\n\
+With --url-encode, stdin is percent-encoded for the --component of a URL: \
+component (the default, like encodeURIComponent), path, segment, query, fragment, \
+userinfo or form. --url-decode decodes it.\n\
+\n\
+With --punycode or --idna, every token is converted to Punycode \
+or to the ASCII form of an international domain name. Use --decode to convert back.\n\
+\n\
+With --normalize, stdin is written in the Unicode normalization form \
+nfc, nfd, nfkc or nfkd. --fold applies full Unicode case folding before normalizing. \
+Add --check to instead exit with an error if the input would change.\n",
         prog = program,
     );
     let usage = opts.usage(&brief);
@@ -32,6 +100,159 @@ fn main() {
     opts.optflag("h", "hex", "parses the input as hexadecimal");
     opts.optflag("d", "decimal", "parses the input as decimal");
     opts.optopt("r", "base", "parses the input as the given base", "BASE");
+    opts.optflag(
+        "a",
+        "auto",
+        "detects the base of each number from its prefix \
+        (0x, 0o, 0b, U+ or \\u{}). Numbers without one use the base flags",
+    );
+    opts.optflag(
+        "",
+        "lossy",
+        "writes U+FFFD for numbers which can't be converted and continues. \
+        A summary of the failures is printed at the end",
+    );
+    opts.optflag(
+        "",
+        "strict",
+        "stops at the first number which can't be converted (default)",
+    );
+    opts.optflag(
+        "e",
+        "encode",
+        "converts text to numbers. The base flags then set the output base",
+    );
+    opts.optflag(
+        "",
+        "decode",
+        "decodes the input of --codec, --punycode or --idna",
+    );
+    opts.optopt(
+        "t",
+        "transform",
+        "runs stdin through the comma-separated STEPS. See the top of --help",
+        "STEPS",
+    );
+    opts.optopt(
+        "u",
+        "unit",
+        "what each number represents: scalar (default), utf8 or utf16",
+        "UNIT",
+    );
+    opts.optflag("n", "no-newline", "don't print a trailing newline");
+    opts.optopt(
+        "o",
+        "output",
+        "the encoding to write the characters in: utf8 (default), bytes, latin1, \
+        utf16le, utf16be, utf32 or utf32le. \
+        In bytes mode, every number is written as one raw byte. \
+        Only utf8 output ends with a newline",
+        "ENCODING",
+    );
+    opts.optopt(
+        "c",
+        "codec",
+        "encodes stdin with base64, base64url, base32, base58, hex or ascii85",
+        "CODEC",
+    );
+    opts.optopt(
+        "",
+        "alphabet",
+        "maps every number to the symbol at that index of ALPHABET, such as 'a-zA-Z0-9'",
+        "ALPHABET",
+    );
+    opts.optopt(
+        "",
+        "alphabet-file",
+        "like --alphabet, but reads one symbol per line from FILE",
+        "FILE",
+    );
+    opts.optflag(
+        "",
+        "names",
+        "also accepts Unicode names (also as \\N{NAME}), HTML entities and U+ code points as tokens",
+    );
+    opts.optopt(
+        "",
+        "pack",
+        "writes every number as a binary TYPE: u8, i8, u16, i16, u32, i32, u64, i64, \
+        f32 or f64, followed by le or be for the byte order (e.g. u16le)",
+        "TYPE",
+    );
+    opts.optopt(
+        "",
+        "unpack",
+        "reads binary values of TYPE and writes them as numbers. See --pack",
+        "TYPE",
+    );
+    opts.optflag("", "dump", "writes stdin as a hexdump, like xxd");
+    opts.optflag(
+        "",
+        "undump",
+        "parses a hexdump back into bytes, like xxd -r",
+    );
+    opts.optopt(
+        "",
+        "columns",
+        "the number of bytes per line of --dump. Default is 16",
+        "COLUMNS",
+    );
+    opts.optopt(
+        "",
+        "group",
+        "the number of bytes per group of hex in --dump. 0 disables grouping. Default is 2",
+        "BYTES",
+    );
+    opts.optopt(
+        "",
+        "escape",
+        "writes stdin as a string literal of LANG: rust, c, json, shell, python, html or url",
+        "LANG",
+    );
+    opts.optopt(
+        "",
+        "unescape",
+        "reads a string literal of LANG and writes its contents. See --escape",
+        "LANG",
+    );
+    opts.optflag("", "url-encode", "percent-encodes stdin. See --component");
+    opts.optflag("", "url-decode", "decodes percent-encoded stdin");
+    opts.optopt(
+        "",
+        "component",
+        "the part of a URL to percent-encode: component (the default), path, segment, \
+        query, fragment, userinfo or form",
+        "COMPONENT",
+    );
+    opts.optflag("", "punycode", "converts every token to Punycode");
+    opts.optflag(
+        "",
+        "idna",
+        "converts every token to an ASCII international domain name",
+    );
+    opts.optopt(
+        "",
+        "normalize",
+        "writes stdin in the Unicode normalization FORM: nfc, nfd, nfkc or nfkd",
+        "FORM",
+    );
+    opts.optflag("", "fold", "applies full Unicode case folding to stdin");
+    opts.optflag(
+        "",
+        "check",
+        "with --normalize or --fold, exits with an error if stdin would change",
+    );
+    opts.optflag(
+        "i",
+        "inspect",
+        "lists the Unicode properties of each character of stdin",
+    );
+    opts.optopt(
+        "w",
+        "wrap",
+        "wrap encoded lines after COLUMNS characters. 0 (the default) disables wrapping",
+        "COLUMNS",
+    );
 
     let matches = match opts.parse(&args[1..]) {
         Ok(m) => m,
@@ -68,45 +289,598 @@ fn main() {
         }
     };
 
-    let mut chars = String::with_capacity(512);
-    let mut buffer = Vec::with_capacity(4096);
-
-    match io::stdin().read_to_end(&mut buffer) {
-        Ok(_) => (),
-        Err(_) => "Failed to read stdin.".print_exit(),
+    let unit = match matches.opt_get_default("u", Unit::Scalar) {
+        Ok(u) => u,
+        Err(err) => err.print_exit(),
+    };
+    let output = match matches.opt_get_default("o", Output::Utf8) {
+        Ok(o) => o,
+        Err(err) => err.print_exit(),
+    };
+    let codec = match matches.opt_get::<Codec>("c") {
+        Ok(c) => c,
+        Err(err) => err.print_exit(),
+    };
+    let transform = match matches
+        .opt_str("t")
+        .map(|s| Step::parse_list(&s))
+        .transpose()
+    {
+        Ok(t) => t,
+        Err(err) => err.print_exit(),
+    };
+    let wrap = match matches.opt_get_default("w", 0_usize) {
+        Ok(w) => w,
+        Err(_) => "Failed to parse the wrap width.".print_exit(),
+    };
+    let pack = match matches.opt_get::<Layout>("pack") {
+        Ok(p) => p,
+        Err(err) => err.print_exit(),
+    };
+    let unpack = match matches.opt_get::<Layout>("unpack") {
+        Ok(p) => p,
+        Err(err) => err.print_exit(),
+    };
+    let escape = match matches.opt_get::<Language>("escape") {
+        Ok(l) => l,
+        Err(err) => err.print_exit(),
+    };
+    let unescape = match matches.opt_get::<Language>("unescape") {
+        Ok(l) => l,
+        Err(err) => err.print_exit(),
+    };
+    let normalize = match matches.opt_get::<Form>("normalize") {
+        Ok(f) => f,
+        Err(err) => err.print_exit(),
+    };
+    let fold = matches.opt_present("fold");
+    let check = matches.opt_present("check");
+    if check && normalize.is_none() && !fold {
+        "Flag `check` only applies with `normalize` or `fold`.".print_exit()
+    }
+    let url_encode = matches.opt_present("url-encode");
+    let url_decode = matches.opt_present("url-decode");
+    let component = match matches.opt_get::<Component>("component") {
+        Ok(c) => c,
+        Err(err) => err.print_exit(),
+    };
+    if component.is_some() && !url_encode && !url_decode {
+        "Flag `component` only applies with `url-encode` or `url-decode`.".print_exit()
+    }
+    let component = component.unwrap_or(Component::Any);
+    let punycode = matches.opt_present("punycode");
+    let idna = matches.opt_present("idna");
+    let auto = matches.opt_present("a");
+    let encode = matches.opt_present("e");
+    let decode = matches.opt_present("decode");
+    if decode && codec.is_none() && !punycode && !idna {
+        "Flag `decode` only applies with `codec`, `punycode` or `idna`.".print_exit()
+    }
+    let inspect = matches.opt_present("i");
+    let dump = matches.opt_present("dump");
+    let undump = matches.opt_present("undump");
+    let columns = match matches.opt_get_default("columns", 16_usize) {
+        Ok(c) if c > 0 => c,
+        _ => "The number of columns must be a positive integer.".print_exit(),
+    };
+    let group = match matches.opt_get_default("group", 2_usize) {
+        Ok(g) => g,
+        Err(_) => "Failed to parse the group size.".print_exit(),
+    };
+    let modes = [
+        encode,
+        codec.is_some(),
+        transform.is_some(),
+        inspect,
+        pack.is_some(),
+        unpack.is_some(),
+        dump,
+        undump,
+        escape.is_some(),
+        unescape.is_some(),
+        url_encode,
+        url_decode,
+        punycode,
+        idna,
+        normalize.is_some() || fold,
+    ];
+    if modes.iter().filter(|mode| **mode).count() > 1 {
+        "Flag `encode`, `codec`, `transform`, `inspect`, `pack`, `unpack`, `dump`, `undump`, \
+        `escape`, `unescape`, \
+        `url-encode`, `url-decode`, `punycode`, `idna` and `normalize` are exclusive. See --help for more info."
+            .print_exit()
+    }
+    if auto && modes.contains(&true) && pack.is_none() {
+        "Flag `auto` only applies when reading numbers.".print_exit()
+    }
+    if encode && decode {
+        "Flag `encode` and `decode` are exclusive. See --help for more info.".print_exit()
+    }
+    if encode && matches.opt_present("o") {
+        "Flag `output` can't be used with `encode`, which always writes numbers.".print_exit()
+    }
+    let alphabet = match (
+        matches.opt_str("alphabet"),
+        matches.opt_str("alphabet-file"),
+    ) {
+        (Some(_), Some(_)) => {
+            "Flag `alphabet` and `alphabet-file` are exclusive. See --help for more info."
+                .print_exit()
+        }
+        (Some(spec), None) => Some(Alphabet::parse(&spec)),
+        (None, Some(path)) => match std::fs::read_to_string(&path) {
+            Ok(text) => Some(Alphabet::from_lines(&text)),
+            Err(_) => format!("Failed to read alphabet file {}.", path).print_exit(),
+        },
+        (None, None) => None,
+    };
+    let alphabet = match alphabet.transpose() {
+        Ok(a) => a,
+        Err(err) => err.print_exit(),
     };
+    if alphabet.is_some() && modes.contains(&true) {
+        "Flag `alphabet` only applies when converting numbers to characters.".print_exit()
+    }
+    let names = matches.opt_present("names");
+    if names && modes.contains(&true) {
+        "Flag `names` only applies when converting numbers to characters.".print_exit()
+    }
+    let lossy = matches.opt_present("lossy");
+    if lossy && matches.opt_present("strict") {
+        "Flag `lossy` and `strict` are exclusive. See --help for more info.".print_exit()
+    }
+    let newline = !matches.opt_present("n") && output.trailing_newline();
+
+    if separator.is_empty() {
+        "The separator can't be empty.".print_exit()
+    }
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
 
-    let string = match String::from_utf8(buffer) {
-        Err(_) => "Failed to convert to utf-8".print_exit(),
-        Ok(s) => s,
+    let result = if dump {
+        transcode(dump::Dump::new(columns, group), &mut stdout)
+            .and_then(|()| finish(&mut stdout, false))
+    } else if undump {
+        transcode(dump::Undump::new(), &mut stdout).and_then(|()| finish(&mut stdout, false))
+    } else if url_encode {
+        transcode(url::UrlEncoder::new(component), &mut stdout)
+            .and_then(|()| finish(&mut stdout, newline))
+    } else if url_decode {
+        transcode(url::UrlDecoder::new(component), &mut stdout)
+            .and_then(|()| finish(&mut stdout, false))
+    } else if punycode || idna {
+        let convert = if punycode { url::punycode } else { url::idna };
+        convert_tokens(&separator, |token| convert(token, decode), &mut stdout)
+            .and_then(|()| finish(&mut stdout, newline))
+    } else if let Some(language) = escape {
+        // Without quotes, a newline would be part of the text.
+        let newline = newline && !language.quote().is_empty();
+        escape_text(language, &mut stdout).and_then(|()| finish(&mut stdout, newline))
+    } else if let Some(language) = unescape {
+        unescape_text(language, &mut stdout).and_then(|()| finish(&mut stdout, false))
+    } else if normalize.is_some() || fold {
+        let normalizer = Normalizer::new(normalize, fold);
+        if check {
+            check_normalized(normalizer)
+        } else {
+            normalize_text(normalizer, &mut stdout).and_then(|()| finish(&mut stdout, false))
+        }
+    } else if inspect {
+        inspect_text(&mut stdout).and_then(|()| finish(&mut stdout, false))
+    } else if let Some(layout) = pack {
+        pack_numbers(&separator, layout, base, auto, &mut stdout)
+            .and_then(|()| finish(&mut stdout, false))
+    } else if let Some(layout) = unpack {
+        unpack_numbers(&separator, layout, base, &mut stdout)
+            .and_then(|()| finish(&mut stdout, newline))
+    } else if let Some(codec) = codec {
+        let mut chain = Chain::new();
+        if decode {
+            chain.push(codec.decoder());
+        } else {
+            chain.push(codec.encoder());
+            if wrap > 0 {
+                chain.push(Box::new(codec::Wrap::new(wrap)));
+            }
+        }
+        // Decoded data is binary, so don't append a newline.
+        let newline = newline && !decode;
+        transcode(chain, &mut stdout).and_then(|()| finish(&mut stdout, newline))
+    } else if let Some(steps) = transform {
+        let mut chain = Chain::new();
+        for step in &steps {
+            chain.push(step.transcoder());
+        }
+        if wrap > 0 {
+            chain.push(Box::new(codec::Wrap::new(wrap)));
+        }
+        // Only the codecs remove the newline of the input.
+        let newline = newline && matches!(steps.last(), Some(Step::Encode(_)));
+        transcode(chain, &mut stdout).and_then(|()| finish(&mut stdout, newline))
+    } else if encode {
+        text_to_numbers(&separator, base, unit, &mut stdout)
+            .and_then(|()| finish(&mut stdout, newline))
+    } else {
+        let conversion = Conversion {
+            base,
+            auto,
+            unit,
+            alphabet: alphabet.as_ref(),
+            names,
+            output,
+        };
+        numbers_to_text(&separator, &conversion, lossy, &mut stdout)
+            .and_then(|()| finish(&mut stdout, newline))
     };
-    for byte in string.split(separator.as_ref()) {
-        let byte = byte.trim();
-        if byte.is_empty() {
+    if let Err(err) = result {
+        err.print_exit()
+    }
+}
+
+fn write_error(_: io::Error) -> String {
+    "Failed to write to stdout.".to_owned()
+}
+
+fn finish(stdout: &mut impl Write, newline: bool) -> Result<(), String> {
+    stdout
+        .write_all(if newline { b"\n" } else { b"" })
+        .and(stdout.flush())
+        .map_err(write_error)
+}
+
+fn transcode(mut transcoder: impl Transcoder, stdout: &mut impl Write) -> Result<(), String> {
+    let mut buf = Vec::with_capacity(8192);
+    let len = stream::read_chunks(FlushingStdin::new(), |chunk, offset| {
+        buf.clear();
+        transcoder.update(chunk, offset, &mut buf)?;
+        stdout.write_all(&buf).map_err(write_error)
+    })?;
+    buf.clear();
+    transcoder.finish(len, &mut buf)?;
+    stdout.write_all(&buf).map_err(write_error)
+}
+
+fn inspect_text(stdout: &mut impl Write) -> Result<(), String> {
+    writeln!(stdout, "{}", inspect::HEADER).map_err(write_error)?;
+    let mut offset = 0;
+    stream::read_text(FlushingStdin::new(), |text| {
+        for c in text.chars() {
+            writeln!(stdout, "{}", inspect::inspect(c, offset)).map_err(write_error)?;
+            offset += c.len_utf8() as u64;
+        }
+        Ok(())
+    })
+}
+
+fn escape_text(language: Language, stdout: &mut impl Write) -> Result<(), String> {
+    stdout
+        .write_all(language.quote().as_bytes())
+        .map_err(write_error)?;
+    let mut escaped = String::new();
+    stream::read_text(FlushingStdin::new(), |text| {
+        escaped.clear();
+        for c in text.chars() {
+            language.escape(c, &mut escaped);
+        }
+        stdout.write_all(escaped.as_bytes()).map_err(write_error)
+    })?;
+    stdout
+        .write_all(language.quote().as_bytes())
+        .map_err(write_error)
+}
+
+/// Reads the whole literal, as escapes can't be resolved without what follows them.
+fn unescape_text(language: Language, stdout: &mut impl Write) -> Result<(), String> {
+    let mut literal = String::new();
+    stream::read_text(FlushingStdin::new(), |text| {
+        literal.push_str(text);
+        Ok(())
+    })?;
+    let bytes = language.unescape(&literal)?;
+    stdout.write_all(&bytes).map_err(write_error)
+}
+
+fn normalize_text(mut normalizer: Normalizer, stdout: &mut impl Write) -> Result<(), String> {
+    let mut normalized = String::new();
+    stream::read_text(FlushingStdin::new(), |text| {
+        normalized.clear();
+        normalizer.push(text, &mut normalized);
+        stdout.write_all(normalized.as_bytes()).map_err(write_error)
+    })?;
+    normalized.clear();
+    normalizer.finish(&mut normalized);
+    stdout.write_all(normalized.as_bytes()).map_err(write_error)
+}
+
+fn check_normalized(mut normalizer: Normalizer) -> Result<(), String> {
+    stream::read_text(FlushingStdin::new(), |text| normalizer.check(text, false))?;
+    normalizer.check("", true)
+}
+
+/// Converts every token with `convert`, writing the results separated by `separator`.
+fn convert_tokens(
+    separator: &str,
+    convert: impl Fn(&str) -> Result<String, String>,
+    stdout: &mut impl Write,
+) -> Result<(), String> {
+    let mut tokens = Tokens::new(FlushingStdin::new(), separator.as_bytes());
+    let mut first = true;
+    loop {
+        let token = match tokens.next_token() {
+            Ok(Some(token)) => token,
+            Ok(None) => return Ok(()),
+            Err(_) => return Err("Failed to read stdin.".to_owned()),
+        };
+        let error = |message: String| {
+            format!(
+                "{} Error at byte {} (token {}).",
+                message, token.offset, token.index
+            )
+        };
+        let text = std::str::from_utf8(token.bytes)
+            .map_err(|_| error("Input is not valid UTF-8.".to_owned()))?
+            .trim();
+        if text.is_empty() {
             continue;
         }
-        let int = match u32::from_str_radix(byte, base) {
-            Ok(i) => i,
-            Err(_) => format!(
-                "Failed to parse '{}' to a integer. Check the base you're using.",
-                byte
+        let converted = convert(text).map_err(error)?;
+        if !first {
+            stdout
+                .write_all(separator.as_bytes())
+                .map_err(write_error)?;
+        }
+        first = false;
+        stdout
+            .write_all(converted.as_bytes())
+            .map_err(write_error)?;
+    }
+}
+
+fn pack_numbers(
+    separator: &str,
+    layout: Layout,
+    base: u32,
+    auto: bool,
+    stdout: &mut impl Write,
+) -> Result<(), String> {
+    let mut tokens = Tokens::new(FlushingStdin::new(), separator.as_bytes());
+    let mut buf = Vec::with_capacity(8);
+    loop {
+        let token = match tokens.next_token() {
+            Ok(Some(token)) => token,
+            Ok(None) => return Ok(()),
+            Err(_) => return Err("Failed to read stdin.".to_owned()),
+        };
+        let error = |message: String| {
+            format!(
+                "{} Error at byte {} (token {}).",
+                message, token.offset, token.index
             )
-            .print_exit(),
         };
-        let char = match char::from_u32(int) {
-            Some(c) => c,
-            None => format!("Failed to convert '{}' to a character.", int).print_exit(),
+        let text = std::str::from_utf8(token.bytes)
+            .map_err(|_| error("Input is not valid UTF-8.".to_owned()))?
+            .trim();
+        if text.is_empty() {
+            continue;
+        }
+        buf.clear();
+        layout.pack(text, base, auto, &mut buf).map_err(error)?;
+        stdout.write_all(&buf).map_err(write_error)?;
+    }
+}
+
+fn unpack_numbers(
+    separator: &str,
+    layout: Layout,
+    base: u32,
+    stdout: &mut impl Write,
+) -> Result<(), String> {
+    let size = layout.size();
+    let mut value = Vec::with_capacity(size);
+    let mut first = true;
+    let len = stream::read_chunks(FlushingStdin::new(), |chunk, _| {
+        let mut numbers = String::with_capacity(chunk.len() * 4);
+        for byte in chunk {
+            value.push(*byte);
+            if value.len() == size {
+                if !first {
+                    numbers.push_str(separator);
+                }
+                first = false;
+                numbers.push_str(&layout.unpack(&value, base));
+                value.clear();
+            }
+        }
+        stdout.write_all(numbers.as_bytes()).map_err(write_error)
+    })?;
+    if value.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "The input ends with {} byte(s) which don't form a whole value, at byte {}.",
+            value.len(),
+            len - value.len() as u64
+        ))
+    }
+}
+
+fn text_to_numbers(
+    separator: &str,
+    base: u32,
+    unit: Unit,
+    stdout: &mut impl Write,
+) -> Result<(), String> {
+    let mut first = true;
+    stream::read_text(FlushingStdin::new(), |text| {
+        let mut numbers = String::with_capacity(text.len() * 4);
+        for c in text.chars() {
+            encode::encode(c, unit, |n| {
+                if !first {
+                    numbers.push_str(separator);
+                }
+                first = false;
+                numbers.push_str(&encode::format_radix(n.into(), base));
+            });
+        }
+        stdout.write_all(numbers.as_bytes()).map_err(write_error)
+    })
+}
+
+/// How numbers are converted to characters.
+struct Conversion<'a> {
+    base: u32,
+    auto: bool,
+    unit: Unit,
+    alphabet: Option<&'a Alphabet>,
+    /// Look up tokens which aren't numbers with [`names::lookup`].
+    names: bool,
+    output: Output,
+}
+impl<'a> Conversion<'a> {
+    /// Converts the trimmed `token` and writes the result.
+    fn convert(
+        &self,
+        token: &str,
+        decoder: &mut Decoder,
+        stdout: &mut impl Write,
+    ) -> Result<(), String> {
+        let int = if self.auto {
+            encode::parse_auto(token, self.base)
+        } else {
+            u32::from_str_radix(token, self.base).ok()
         };
-        chars.push(char);
+        let int = match int {
+            Some(int) => int,
+            None if self.names => {
+                for c in names::lookup(token)?.chars() {
+                    write_char(c, self.output, stdout)?;
+                }
+                return Ok(());
+            }
+            None => {
+                return Err(format!(
+                    "Failed to parse '{}' to a integer. Check the base you're using.",
+                    token
+                ))
+            }
+        };
+        if let Some(alphabet) = self.alphabet {
+            for c in alphabet.get(int)?.chars() {
+                write_char(c, self.output, stdout)?;
+            }
+            return Ok(());
+        }
+        if self.output == Output::Bytes {
+            let byte = u8::try_from(int)
+                .map_err(|_| format!("'{}' is too large to be written as a byte.", int))?;
+            return stdout.write_all(&[byte]).map_err(write_error);
+        }
+        match decoder.push(int)? {
+            Some(c) => write_char(c, self.output, stdout),
+            None => Ok(()),
+        }
     }
+}
 
-    let mut stdout = io::stdout();
-    if stdout
-        .write_all(chars.as_bytes())
-        .and(stdout.write(b"\n"))
-        .and(stdout.flush())
-        .is_err()
-    {
-        "Failed to write to stdout.".print_exit()
+/// A token which failed to convert in lossy mode.
+struct Failure {
+    index: usize,
+    offset: u64,
+    text: String,
+    message: String,
+}
+
+fn numbers_to_text(
+    separator: &str,
+    conversion: &Conversion,
+    lossy: bool,
+    stdout: &mut impl Write,
+) -> Result<(), String> {
+    let mut tokens = Tokens::new(FlushingStdin::new(), separator.as_bytes());
+    let mut decoder = Decoder::new(conversion.unit);
+    let mut failures = Vec::new();
+    let mut fail = |failure: Failure, stdout: &mut dyn Write| {
+        if !lossy {
+            return Err(format!(
+                "{} Error at byte {} (token {}).",
+                failure.message, failure.offset, failure.index
+            ));
+        }
+        // Not all outputs can represent the replacement character.
+        write_char(char::REPLACEMENT_CHARACTER, conversion.output, stdout)
+            .or_else(|_| write_char('?', conversion.output, stdout))?;
+        failures.push(failure);
+        Ok(())
+    };
+    let mut last_index = 0;
+    let mut end = 0;
+    loop {
+        let token = match tokens.next_token() {
+            Ok(Some(token)) => token,
+            Ok(None) => break,
+            Err(_) => return Err("Failed to read stdin.".to_owned()),
+        };
+        last_index = token.index;
+        end = token.offset + token.bytes.len() as u64;
+        let text = match std::str::from_utf8(token.bytes) {
+            Ok(text) => text,
+            Err(err) => {
+                fail(
+                    Failure {
+                        index: token.index,
+                        offset: token.offset + err.valid_up_to() as u64,
+                        text: String::from_utf8_lossy(token.bytes).trim().to_owned(),
+                        message: "Input is not valid UTF-8.".to_owned(),
+                    },
+                    stdout,
+                )?;
+                continue;
+            }
+        };
+        let trimmed = text.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if let Err(message) = conversion.convert(trimmed, &mut decoder, stdout) {
+            fail(
+                Failure {
+                    index: token.index,
+                    offset: token.offset + (text.len() - text.trim_start().len()) as u64,
+                    text: trimmed.to_owned(),
+                    message,
+                },
+                stdout,
+            )?;
+        }
     }
+    if let Err(message) = decoder.finish() {
+        fail(
+            Failure {
+                index: last_index,
+                offset: end,
+                text: String::new(),
+                message,
+            },
+            stdout,
+        )?;
+    }
+    if !failures.is_empty() {
+        // Get the converted text out before the summary.
+        stdout.flush().map_err(write_error)?;
+        eprintln!("\nFailed to convert {} token(s):", failures.len());
+        for failure in failures {
+            eprintln!(
+                "token {} at byte {}: '{}': {}",
+                failure.index, failure.offset, failure.text, failure.message
+            );
+        }
+    }
+    Ok(())
+}
+
+fn write_char(c: char, output: Output, stdout: &mut (impl Write + ?Sized)) -> Result<(), String> {
+    let mut buf = [0; 4];
+    let bytes = output.encode(c, &mut buf)?;
+    stdout.write_all(bytes).map_err(write_error)
 }
@@ -0,0 +1,197 @@
+//! Lookup of characters by Unicode name, HTML entity or `U+` code point.
+
+use std::borrow::Cow;
+use std::sync::OnceLock;
+
+use entities::ENTITIES;
+
+/// The number of close matches suggested for an unknown name.
+const SUGGESTIONS: usize = 3;
+
+/// Returns the characters of `token`, which is one of
+///
+/// - `U+<hex digits>`, such as `U+1F600`
+/// - an HTML entity, such as `&eacute;` or `&#233;`
+/// - a Unicode name or alias, such as `LATIN SMALL LETTER A WITH ACUTE`,
+///   optionally in a `\N{...}` escape as in Python
+///
+/// All are case-insensitive, though entities which only differ in case are
+/// first matched exactly.
+pub fn lookup(token: &str) -> Result<Cow<'static, str>, String> {
+    let token = token
+        .strip_prefix("\\N{")
+        .or_else(|| token.strip_prefix("\\n{"))
+        .and_then(|name| name.strip_suffix('}'))
+        .unwrap_or(token);
+    if let Some(hex) = token
+        .strip_prefix("U+")
+        .or_else(|| token.strip_prefix("u+"))
+    {
+        return u32::from_str_radix(hex, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .map(|c| Cow::Owned(c.to_string()))
+            .ok_or_else(|| format!("'{}' is not a valid code point.", token));
+    }
+    if token.starts_with('&') {
+        return lookup_entity(token);
+    }
+    if let Some(c) = unicode_names2::character(token) {
+        return Ok(Cow::Owned(c.to_string()));
+    }
+    let query = token.to_ascii_uppercase().replace('_', " ");
+    let names = all_names().iter().map(String::as_str);
+    Err(unknown("character name", token, closest(&query, names)))
+}
+
+/// Returns the characters of an HTML entity, such as `&amp;`, with a matching case.
+pub fn entity(entity: &str) -> Option<&'static str> {
+    ENTITIES
+        .iter()
+        .find(|e| e.entity == entity)
+        .map(|e| e.characters)
+}
+
+fn lookup_entity(token: &str) -> Result<Cow<'static, str>, String> {
+    if let Some(number) = token
+        .strip_prefix("&#")
+        .map(|number| number.strip_suffix(';').unwrap_or(number))
+    {
+        let value = match number.strip_prefix(['x', 'X'].as_ref()) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+            None => number.parse().ok(),
+        };
+        return value
+            .and_then(char::from_u32)
+            .map(|c| Cow::Owned(c.to_string()))
+            .ok_or_else(|| format!("'{}' is not a valid numeric entity.", token));
+    }
+    // Some entities are also valid without the semicolon, but only add it if there's a match.
+    let with_semicolon = if token.ends_with(';') {
+        Cow::Borrowed(token)
+    } else {
+        Cow::Owned(format!("{};", token))
+    };
+    let found = entity(token)
+        .or_else(|| entity(&with_semicolon))
+        .or_else(|| {
+            ENTITIES
+                .iter()
+                .find(|e| e.entity.eq_ignore_ascii_case(&with_semicolon))
+                .map(|e| e.characters)
+        });
+    if let Some(characters) = found {
+        return Ok(Cow::Borrowed(characters));
+    }
+    let names = ENTITIES
+        .iter()
+        .filter(|e| e.entity.ends_with(';'))
+        .map(|e| e.entity);
+    Err(unknown("entity", token, closest(&with_semicolon, names)))
+}
+
+/// The names of all characters, collected once as it takes a while.
+fn all_names() -> &'static [String] {
+    static NAMES: OnceLock<Vec<String>> = OnceLock::new();
+    NAMES.get_or_init(|| {
+        (0..=0x10ffff_u32)
+            .filter_map(char::from_u32)
+            .filter_map(unicode_names2::name)
+            .map(|name| name.to_string())
+            .collect()
+    })
+}
+
+fn unknown(kind: &str, token: &str, suggestions: Vec<String>) -> String {
+    match suggestions.split_last() {
+        None => format!("Unknown {} '{}'.", kind, token),
+        Some((last, [])) => format!("Unknown {} '{}'. Did you mean {}?", kind, token, last),
+        Some((last, rest)) => format!(
+            "Unknown {} '{}'. Did you mean {} or {}?",
+            kind,
+            token,
+            rest.join(", "),
+            last
+        ),
+    }
+}
+
+/// Returns the [`SUGGESTIONS`] candidates closest to `query`, ignoring case,
+/// which aren't too different to be relevant.
+fn closest<'a>(query: &str, candidates: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let query_len = query.chars().count();
+    let max = (query_len / 4).max(2);
+    let query_counts = counts(query);
+    let mut matches: Vec<(usize, &str)> = candidates
+        // There are many candidates, so first rule out most with cheap lower bounds.
+        .filter(|candidate| {
+            let len = candidate.chars().count();
+            len + max >= query_len && len <= query_len + max
+        })
+        .filter(|candidate| {
+            // Every character missing from one of the strings takes an edit.
+            let (mut missing, mut extra) = (0, 0);
+            for (query, candidate) in query_counts.iter().zip(counts(candidate).iter()) {
+                if query > candidate {
+                    missing += (query - candidate) as usize;
+                } else {
+                    extra += (candidate - query) as usize;
+                }
+            }
+            missing.max(extra) <= max
+        })
+        .filter_map(|candidate| {
+            let distance = distance(query, candidate, true);
+            if distance <= max {
+                Some((distance, candidate))
+            } else {
+                None
+            }
+        })
+        .collect();
+    // Prefer the candidates matching the case of the query.
+    matches.sort_by_cached_key(|(distance, candidate)| {
+        (*distance, self::distance(query, candidate, false))
+    });
+    matches
+        .into_iter()
+        .take(SUGGESTIONS)
+        .map(|(_, candidate)| candidate.to_owned())
+        .collect()
+}
+
+/// The number of each ASCII character in `s`, ignoring case. Other characters are counted
+/// together.
+fn counts(s: &str) -> [u16; 128] {
+    let mut counts = [0_u16; 128];
+    for c in s.chars() {
+        let index = if c.is_ascii() {
+            c.to_ascii_lowercase() as usize
+        } else {
+            0
+        };
+        counts[index] = counts[index].saturating_add(1);
+    }
+    counts
+}
+
+/// The Levenshtein distance between `a` and `b`, optionally ignoring ASCII case.
+fn distance(a: &str, b: &str, ignore_case: bool) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, a) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let same = if ignore_case {
+                a.eq_ignore_ascii_case(b)
+            } else {
+                a == *b
+            };
+            let substitution = previous[j] + usize::from(!same);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
@@ -0,0 +1,165 @@
+//! Unicode normalization and case folding of streamed text.
+
+use std::str::FromStr;
+
+use unicode_normalization::char::{
+    canonical_combining_class, decompose_canonical, decompose_compatible,
+};
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Form {
+    Nfc,
+    Nfd,
+    Nfkc,
+    Nfkd,
+}
+impl FromStr for Form {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "nfc" => Ok(Self::Nfc),
+            "nfd" => Ok(Self::Nfd),
+            "nfkc" => Ok(Self::Nfkc),
+            "nfkd" => Ok(Self::Nfkd),
+            _ => Err(format!(
+                "Normalization form '{}' is not valid. Available are: nfc, nfd, nfkc and nfkd.",
+                s
+            )),
+        }
+    }
+}
+impl Form {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Nfc => "NFC",
+            Self::Nfd => "NFD",
+            Self::Nfkc => "NFKC",
+            Self::Nfkd => "NFKD",
+        }
+    }
+}
+
+/// Case folds (if enabled) and then normalizes text given in arbitrary pieces.
+///
+/// As combining characters can change the characters before them, the text from the last
+/// starter which never combines with what precedes it is held back until more text arrives.
+pub struct Normalizer {
+    form: Option<Form>,
+    fold: bool,
+    pending: String,
+    /// The byte offset of `pending` in the input.
+    offset: u64,
+}
+impl Normalizer {
+    pub fn new(form: Option<Form>, fold: bool) -> Self {
+        Self {
+            form,
+            fold,
+            pending: String::new(),
+            offset: 0,
+        }
+    }
+
+    /// Appends the part of `text` which is ready, transformed, to `output`.
+    pub fn push(&mut self, text: &str, output: &mut String) {
+        let ready = self.ready(text, false);
+        self.apply(&ready, output);
+    }
+    /// Appends the rest of the text to `output`.
+    pub fn finish(&mut self, output: &mut String) {
+        let ready = self.ready("", true);
+        self.apply(&ready, output);
+    }
+    /// Returns an error with the byte offset of the first change if the transformation changes
+    /// `text`. Call with `last` set for the final piece of text.
+    pub fn check(&mut self, text: &str, last: bool) -> Result<(), String> {
+        let offset = self.offset;
+        let ready = self.ready(text, last);
+        let mut transformed = String::with_capacity(ready.len());
+        self.apply(&ready, &mut transformed);
+        if transformed == ready {
+            return Ok(());
+        }
+        let same = ready
+            .char_indices()
+            .zip(transformed.chars())
+            .find(|((_, original), new)| original != new)
+            .map_or(ready.len(), |((pos, _), _)| pos);
+        Err(format!(
+            "Input is not {} at byte {}.",
+            self.describe(),
+            offset + same as u64
+        ))
+    }
+
+    /// Adds `text` and removes the text which can be transformed from the pending text.
+    fn ready(&mut self, text: &str, last: bool) -> String {
+        self.pending.push_str(text);
+        let split = if last {
+            self.pending.len()
+        } else {
+            self.pending
+                .char_indices()
+                .rev()
+                .find(|(_, c)| self.is_boundary(*c))
+                .map_or(0, |(pos, _)| pos)
+        };
+        let rest = self.pending.split_off(split);
+        self.offset += split as u64;
+        std::mem::replace(&mut self.pending, rest)
+    }
+    /// Whether the text before `c` can be transformed without knowing `c` and what follows,
+    /// which is when (the decomposition of) `c` starts with a starter that doesn't compose
+    /// with the preceding characters.
+    fn is_boundary(&self, c: char) -> bool {
+        let c = if self.fold {
+            let mut buf = [0; 4];
+            let folded = caseless::default_case_fold_str(c.encode_utf8(&mut buf));
+            folded.chars().next().unwrap_or(c)
+        } else {
+            c
+        };
+        let mut first = None;
+        let mut keep_first = |d: char| {
+            first.get_or_insert(d);
+        };
+        match self.form {
+            Some(Form::Nfkc) | Some(Form::Nfkd) => decompose_compatible(c, &mut keep_first),
+            _ => decompose_canonical(c, &mut keep_first),
+        }
+        let first = first.unwrap_or(c);
+        canonical_combining_class(first) == 0
+            && match self.form {
+                Some(Form::Nfc) | Some(Form::Nfkc) => {
+                    is_nfc_quick(std::iter::once(first)) == IsNormalized::Yes
+                }
+                _ => true,
+            }
+    }
+    fn apply(&self, text: &str, output: &mut String) {
+        let folded;
+        let text = if self.fold {
+            folded = caseless::default_case_fold_str(text);
+            &folded
+        } else {
+            text
+        };
+        match self.form {
+            Some(Form::Nfc) => output.extend(text.nfc()),
+            Some(Form::Nfd) => output.extend(text.nfd()),
+            Some(Form::Nfkc) => output.extend(text.nfkc()),
+            Some(Form::Nfkd) => output.extend(text.nfkd()),
+            None => output.push_str(text),
+        }
+    }
+    fn describe(&self) -> String {
+        match (self.fold, self.form) {
+            (true, Some(form)) => format!("case folded and in {}", form.name()),
+            (true, None) => "case folded".to_owned(),
+            (false, Some(form)) => format!("in {}", form.name()),
+            // Nothing is transformed, so `check` never fails.
+            (false, None) => unreachable!("nothing to check"),
+        }
+    }
+}
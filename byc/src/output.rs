@@ -0,0 +1,82 @@
+//! Target encodings of the characters `byc` writes.
+
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+/// How characters are written to stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+    Utf8,
+    /// Every number as one raw octet, bypassing characters.
+    Bytes,
+    Latin1,
+    Utf16Le,
+    Utf16Be,
+    Utf32Le,
+    Utf32Be,
+}
+impl FromStr for Output {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "utf8" | "utf-8" => Ok(Self::Utf8),
+            "bytes" | "raw" => Ok(Self::Bytes),
+            "latin1" | "latin-1" | "iso-8859-1" => Ok(Self::Latin1),
+            "utf16le" | "utf-16le" => Ok(Self::Utf16Le),
+            "utf16be" | "utf-16be" => Ok(Self::Utf16Be),
+            "utf32le" | "utf-32le" => Ok(Self::Utf32Le),
+            "utf32" | "utf-32" | "utf32be" | "utf-32be" => Ok(Self::Utf32Be),
+            _ => Err(format!(
+                "Output '{}' is not valid. Available are: \
+                utf8, bytes, latin1, utf16le, utf16be, utf32 (big endian) and utf32le.",
+                s
+            )),
+        }
+    }
+}
+impl Output {
+    /// Encodes `c` into `buf`, returning the bytes to write.
+    ///
+    /// Returns an error message if `c` can't be represented.
+    pub fn encode<'a>(&self, c: char, buf: &'a mut [u8; 4]) -> Result<&'a [u8], String> {
+        let len = match self {
+            Self::Utf8 => c.encode_utf8(buf).len(),
+            Self::Bytes | Self::Latin1 => {
+                buf[0] = u8::try_from(c as u32).map_err(|_| {
+                    format!(
+                        "'{}' (U+{:04X}) can't be written as a single byte.",
+                        c, c as u32
+                    )
+                })?;
+                1
+            }
+            Self::Utf16Le | Self::Utf16Be => {
+                let mut units = [0; 2];
+                let units = c.encode_utf16(&mut units);
+                for (pos, unit) in units.iter().enumerate() {
+                    let bytes = if *self == Self::Utf16Le {
+                        unit.to_le_bytes()
+                    } else {
+                        unit.to_be_bytes()
+                    };
+                    buf[pos * 2..pos * 2 + 2].copy_from_slice(&bytes);
+                }
+                units.len() * 2
+            }
+            Self::Utf32Le => {
+                *buf = (c as u32).to_le_bytes();
+                4
+            }
+            Self::Utf32Be => {
+                *buf = (c as u32).to_be_bytes();
+                4
+            }
+        };
+        Ok(&buf[..len])
+    }
+    /// Whether a newline is written after the output.
+    /// Only text in the terminal's encoding gets one, as it would corrupt binary output.
+    pub fn trailing_newline(&self) -> bool {
+        *self == Self::Utf8
+    }
+}
@@ -0,0 +1,229 @@
+//! Packing of numbers into fixed-width binary values and back.
+
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+use crate::encode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    I64,
+    F32,
+    F64,
+}
+
+/// A fixed-width number type and its byte order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    kind: Kind,
+    little_endian: bool,
+}
+impl FromStr for Layout {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.trim().to_ascii_lowercase();
+        let (name, little_endian) = if let Some(name) = lower.strip_suffix("le") {
+            (name, Some(true))
+        } else if let Some(name) = lower.strip_suffix("be") {
+            (name, Some(false))
+        } else {
+            (lower.as_str(), None)
+        };
+        let kind = match name {
+            "u8" => Kind::U8,
+            "i8" => Kind::I8,
+            "u16" => Kind::U16,
+            "i16" => Kind::I16,
+            "u32" => Kind::U32,
+            "i32" => Kind::I32,
+            "u64" => Kind::U64,
+            "i64" => Kind::I64,
+            "f32" => Kind::F32,
+            "f64" => Kind::F64,
+            _ => {
+                return Err(format!(
+                    "Type '{}' is not valid. Available are: \
+                    u8, i8, u16, i16, u32, i32, u64, i64, f32 and f64, \
+                    followed by le or be for the byte order.",
+                    s
+                ))
+            }
+        };
+        let layout = Self {
+            kind,
+            little_endian: little_endian.unwrap_or(true),
+        };
+        if little_endian.is_none() && layout.size() > 1 {
+            return Err(format!(
+                "Type '{}' needs a byte order. Use {}le or {}be.",
+                s, name, name
+            ));
+        }
+        Ok(layout)
+    }
+}
+impl Layout {
+    /// The number of bytes of a value.
+    pub fn size(&self) -> usize {
+        match self.kind {
+            Kind::U8 | Kind::I8 => 1,
+            Kind::U16 | Kind::I16 => 2,
+            Kind::U32 | Kind::I32 | Kind::F32 => 4,
+            Kind::U64 | Kind::I64 | Kind::F64 => 8,
+        }
+    }
+    fn is_float(&self) -> bool {
+        matches!(self.kind, Kind::F32 | Kind::F64)
+    }
+
+    /// Parses `token` and appends its bytes to `output`.
+    ///
+    /// Integers are parsed in `base`, or with the base detected from their prefix if `auto`.
+    /// Floats are always decimal.
+    pub fn pack(
+        &self,
+        token: &str,
+        base: u32,
+        auto: bool,
+        output: &mut Vec<u8>,
+    ) -> Result<(), String> {
+        let invalid = || {
+            format!(
+                "Failed to parse '{}' to a {}. Check the base you're using.",
+                token,
+                self.name()
+            )
+        };
+        if self.is_float() {
+            // Parse each width directly, as rounding to f64 first could round an f32 twice.
+            match self.kind {
+                Kind::F32 => {
+                    let float: f32 = token.parse().map_err(|_| invalid())?;
+                    self.extend(output, &float.to_le_bytes(), &float.to_be_bytes())
+                }
+                _ => {
+                    let float: f64 = token.parse().map_err(|_| invalid())?;
+                    self.extend(output, &float.to_le_bytes(), &float.to_be_bytes())
+                }
+            }
+            return Ok(());
+        }
+        let (negative, digits) = match token.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, token),
+        };
+        let (digits, base) = if auto {
+            encode::split_prefix(digits, base).ok_or_else(invalid)?
+        } else {
+            (digits, base)
+        };
+        if digits.starts_with(['+', '-'].as_ref()) {
+            return Err(invalid());
+        }
+        let magnitude = u128::from_str_radix(digits, base).map_err(|_| invalid())?;
+        let value = i128::try_from(magnitude)
+            .ok()
+            .map(|v| if negative { -v } else { v })
+            .ok_or_else(|| self.out_of_range(token))?;
+        let range = || self.out_of_range(token);
+        match self.kind {
+            Kind::U8 => self.extend(output, &[u8::try_from(value).map_err(|_| range())?], &[]),
+            Kind::I8 => {
+                let v = i8::try_from(value).map_err(|_| range())?;
+                self.extend(output, &v.to_le_bytes(), &v.to_be_bytes())
+            }
+            Kind::U16 => {
+                let v = u16::try_from(value).map_err(|_| range())?;
+                self.extend(output, &v.to_le_bytes(), &v.to_be_bytes())
+            }
+            Kind::I16 => {
+                let v = i16::try_from(value).map_err(|_| range())?;
+                self.extend(output, &v.to_le_bytes(), &v.to_be_bytes())
+            }
+            Kind::U32 => {
+                let v = u32::try_from(value).map_err(|_| range())?;
+                self.extend(output, &v.to_le_bytes(), &v.to_be_bytes())
+            }
+            Kind::I32 => {
+                let v = i32::try_from(value).map_err(|_| range())?;
+                self.extend(output, &v.to_le_bytes(), &v.to_be_bytes())
+            }
+            Kind::U64 => {
+                let v = u64::try_from(value).map_err(|_| range())?;
+                self.extend(output, &v.to_le_bytes(), &v.to_be_bytes())
+            }
+            Kind::I64 => {
+                let v = i64::try_from(value).map_err(|_| range())?;
+                self.extend(output, &v.to_le_bytes(), &v.to_be_bytes())
+            }
+            Kind::F32 | Kind::F64 => unreachable!("floats are handled above"),
+        }
+        Ok(())
+    }
+    fn extend(&self, output: &mut Vec<u8>, le: &[u8], be: &[u8]) {
+        if self.little_endian || be.is_empty() {
+            output.extend_from_slice(le)
+        } else {
+            output.extend_from_slice(be)
+        }
+    }
+    fn out_of_range(&self, token: &str) -> String {
+        format!("'{}' doesn't fit in a {}.", token, self.name())
+    }
+    fn name(&self) -> &'static str {
+        match self.kind {
+            Kind::U8 => "u8",
+            Kind::I8 => "i8",
+            Kind::U16 => "u16",
+            Kind::I16 => "i16",
+            Kind::U32 => "u32",
+            Kind::I32 => "i32",
+            Kind::U64 => "u64",
+            Kind::I64 => "i64",
+            Kind::F32 => "f32",
+            Kind::F64 => "f64",
+        }
+    }
+
+    /// Formats the value in `bytes`, which has to be [`Self::size`] long.
+    /// Integers are formatted in `base`.
+    pub fn unpack(&self, bytes: &[u8], base: u32) -> String {
+        debug_assert_eq!(bytes.len(), self.size());
+        let mut buf = [0; 8];
+        if self.little_endian {
+            buf[..bytes.len()].copy_from_slice(bytes);
+        } else {
+            // Reverse into little endian, so we only have to handle one byte order.
+            for (dest, src) in buf.iter_mut().zip(bytes.iter().rev()) {
+                *dest = *src;
+            }
+        }
+        let unsigned = u64::from_le_bytes(buf);
+        let signed = |bits: u32| {
+            // Sign-extend the value from `bits`.
+            let shift = 64 - bits;
+            ((unsigned << shift) as i64 >> shift) as i128
+        };
+        let value = match self.kind {
+            Kind::F32 => return f32::from_bits(unsigned as u32).to_string(),
+            Kind::F64 => return f64::from_bits(unsigned).to_string(),
+            Kind::U8 | Kind::U16 | Kind::U32 | Kind::U64 => unsigned as i128,
+            Kind::I8 => signed(8),
+            Kind::I16 => signed(16),
+            Kind::I32 => signed(32),
+            Kind::I64 => signed(64),
+        };
+        if value < 0 {
+            format!("-{}", encode::format_radix(value.unsigned_abs(), base))
+        } else {
+            encode::format_radix(value as u128, base)
+        }
+    }
+}
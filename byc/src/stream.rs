@@ -0,0 +1,187 @@
+//! Streaming input, so `byc` can take part in long-running pipes.
+
+use std::io::{self, Read, Write};
+
+/// Stdin which flushes stdout before blocking on a read.
+///
+/// This makes sure everything converted so far is written before waiting for more input.
+pub struct FlushingStdin(io::Stdin);
+impl FlushingStdin {
+    pub fn new() -> Self {
+        Self(io::stdin())
+    }
+}
+impl Read for FlushingStdin {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        io::stdout().flush()?;
+        self.0.read(buf)
+    }
+}
+
+/// A token from [`Tokens`].
+#[derive(Debug, Clone, Copy)]
+pub struct Token<'a> {
+    pub bytes: &'a [u8],
+    /// The byte offset of the start of the token in the stream.
+    pub offset: u64,
+    /// The zero-based index of the token.
+    pub index: usize,
+}
+
+/// Splits `reader` by `separator` in chunks,
+/// handling separators which cross the boundaries of reads.
+pub struct Tokens<R> {
+    reader: R,
+    separator: Vec<u8>,
+    buf: Vec<u8>,
+    start: usize,
+    end: usize,
+    /// Bytes discarded before `buf[0]`.
+    consumed: u64,
+    index: usize,
+    eof: bool,
+    done: bool,
+}
+impl<R: Read> Tokens<R> {
+    /// `separator` must not be empty.
+    pub fn new(reader: R, separator: &[u8]) -> Self {
+        assert!(!separator.is_empty());
+        Self {
+            reader,
+            separator: separator.to_vec(),
+            buf: vec![0; 4096 + separator.len()],
+            start: 0,
+            end: 0,
+            consumed: 0,
+            index: 0,
+            eof: false,
+            done: false,
+        }
+    }
+
+    fn find_separator(&self, from: usize) -> Option<usize> {
+        let sep = &self.separator[..];
+        self.buf[from..self.end]
+            .windows(sep.len())
+            .position(|window| window == sep)
+            .map(|pos| pos + from)
+    }
+
+    /// Returns the next token, or [`None`] if the input is exhausted.
+    pub fn next_token(&mut self) -> io::Result<Option<Token<'_>>> {
+        // Where to continue searching, so we don't rescan the whole buffer after every read.
+        let mut search_from = self.start;
+        loop {
+            if let Some(pos) = self.find_separator(search_from) {
+                let start = self.start;
+                self.start = pos + self.separator.len();
+                return Ok(Some(self.token(start, pos)));
+            }
+            if self.eof {
+                if self.done {
+                    return Ok(None);
+                }
+                // The rest after the last separator, as with [`str::split`].
+                self.done = true;
+                let start = self.start;
+                self.start = self.end;
+                return Ok(Some(self.token(start, self.end)));
+            }
+            let kept = self.end - self.start;
+            search_from = kept.saturating_sub(self.separator.len() - 1);
+            self.buf.copy_within(self.start..self.end, 0);
+            self.consumed += self.start as u64;
+            self.start = 0;
+            self.end = kept;
+            if self.end == self.buf.len() {
+                let len = self.buf.len();
+                self.buf.resize(len * 2, 0);
+            }
+            let read = loop {
+                match self.reader.read(&mut self.buf[self.end..]) {
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                    result => break result?,
+                }
+            };
+            if read == 0 {
+                self.eof = true;
+            }
+            self.end += read;
+        }
+    }
+    fn token(&mut self, start: usize, end: usize) -> Token<'_> {
+        let index = self.index;
+        self.index += 1;
+        Token {
+            bytes: &self.buf[start..end],
+            offset: self.consumed + start as u64,
+            index,
+        }
+    }
+}
+
+/// Reads `reader` as UTF-8 in chunks and calls `f` with each decoded piece of text.
+///
+/// Characters split between two reads are kept until the rest arrives.
+/// On invalid UTF-8, an error message with the byte offset is returned.
+pub fn read_text<R: Read>(
+    mut reader: R,
+    mut f: impl FnMut(&str) -> Result<(), String>,
+) -> Result<(), String> {
+    let mut buf = vec![0; 4096];
+    let mut left = 0;
+    let mut offset = 0_u64;
+    loop {
+        let read = loop {
+            match reader.read(&mut buf[left..]) {
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(_) => return Err("Failed to read stdin.".to_owned()),
+                Ok(read) => break read,
+            }
+        };
+        let len = left + read;
+        let valid = match std::str::from_utf8(&buf[..len]) {
+            Ok(s) => s.len(),
+            Err(err) => {
+                // An incomplete character at the end of the input is only an error if no more
+                // input follows.
+                if err.error_len().is_some() || read == 0 {
+                    return Err(format!(
+                        "Input is not valid UTF-8 at byte {}.",
+                        offset + err.valid_up_to() as u64
+                    ));
+                }
+                err.valid_up_to()
+            }
+        };
+        // UNWRAP: `valid` is the length of the valid UTF-8 prefix.
+        let text = std::str::from_utf8(&buf[..valid]).unwrap();
+        f(text)?;
+        if read == 0 {
+            return Ok(());
+        }
+        buf.copy_within(valid..len, 0);
+        offset += valid as u64;
+        left = len - valid;
+    }
+}
+
+/// Reads `reader` in chunks and calls `f` with each chunk and the byte offset of its start.
+/// Returns the length of the input.
+pub fn read_chunks<R: Read>(
+    mut reader: R,
+    mut f: impl FnMut(&[u8], u64) -> Result<(), String>,
+) -> Result<u64, String> {
+    let mut buf = vec![0; 4096];
+    let mut offset = 0_u64;
+    loop {
+        let read = match reader.read(&mut buf) {
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(_) => return Err("Failed to read stdin.".to_owned()),
+            Ok(0) => return Ok(offset),
+            Ok(read) => read,
+        };
+        f(&buf[..read], offset)?;
+        offset += read as u64;
+    }
+}
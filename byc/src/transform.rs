@@ -0,0 +1,186 @@
+//! Simple ciphers and other byte transforms, chainable with the codecs.
+
+use std::str::FromStr;
+
+use crate::codec::{Codec, Transcoder};
+
+/// A step of `--transform`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    Rot13,
+    Rot47,
+    /// Shifts letters by the number of positions, in `0..26`.
+    Caesar(u8),
+    Atbash,
+    /// XORs the stream with the key, repeated.
+    Xor(Vec<u8>),
+    Reverse,
+    Encode(Codec),
+    Decode(Codec),
+}
+impl FromStr for Step {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (name, argument) = match s.find(':') {
+            Some(colon) => (&s[..colon], Some(&s[colon + 1..])),
+            None => (s, None),
+        };
+        match (name.to_ascii_lowercase().as_str(), argument) {
+            ("rot13", None) => Ok(Self::Rot13),
+            ("rot47", None) => Ok(Self::Rot47),
+            ("caesar", Some(shift)) => shift
+                .trim()
+                .parse::<i64>()
+                .map(|shift| Self::Caesar(shift.rem_euclid(26) as u8))
+                .map_err(|_| format!("Failed to parse the shift of '{}'.", s)),
+            ("atbash", None) => Ok(Self::Atbash),
+            ("xor", Some(key)) => parse_key(key).map(Self::Xor),
+            ("reverse", None) => Ok(Self::Reverse),
+            ("decode", Some(codec)) => codec.parse().map(Self::Decode),
+            (_, None) => s.parse().map(Self::Encode).map_err(|_| {
+                format!(
+                    "Transform '{}' is not valid. Available are: \
+                    rot13, rot47, caesar:N, atbash, xor:KEY and reverse, \
+                    or a codec to encode with, or decode:CODEC.",
+                    s
+                )
+            }),
+            (_, Some(_)) => Err(format!(
+                "Transform '{}' is not valid. Only caesar, xor and decode take an argument.",
+                s
+            )),
+        }
+    }
+}
+impl Step {
+    /// Parses a comma-separated list of steps.
+    pub fn parse_list(s: &str) -> Result<Vec<Self>, String> {
+        s.split(',').map(str::parse).collect()
+    }
+    pub fn transcoder(&self) -> Box<dyn Transcoder> {
+        match self {
+            Self::Rot13 => Box::new(ByteMap::letters(|c| (c + 13) % 26)),
+            Self::Caesar(shift) => {
+                let shift = *shift;
+                Box::new(ByteMap::letters(move |c| (c + shift) % 26))
+            }
+            Self::Atbash => Box::new(ByteMap::letters(|c| 25 - c)),
+            Self::Rot47 => Box::new(ByteMap::new(|b| match b {
+                b'!'..=b'~' => b'!' + (b - b'!' + 47) % 94,
+                _ => b,
+            })),
+            Self::Xor(key) => Box::new(Xor {
+                key: key.clone(),
+                position: 0,
+            }),
+            Self::Reverse => Box::new(Reverse::default()),
+            Self::Encode(codec) => codec.encoder(),
+            Self::Decode(codec) => codec.decoder(),
+        }
+    }
+}
+
+/// Parses a key of `0x` followed by hex digits, or else takes the text as it is.
+fn parse_key(key: &str) -> Result<Vec<u8>, String> {
+    let bytes = match key.strip_prefix("0x").or_else(|| key.strip_prefix("0X")) {
+        Some(hex) => {
+            if !hex.len().is_multiple_of(2) {
+                return Err(format!(
+                    "The XOR key '{}' has an odd number of hex digits.",
+                    key
+                ));
+            }
+            (0..hex.len())
+                .step_by(2)
+                .map(|pos| {
+                    hex.get(pos..pos + 2)
+                        .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                })
+                .collect::<Option<Vec<u8>>>()
+                .ok_or_else(|| format!("The XOR key '{}' isn't valid hex.", key))?
+        }
+        None => key.as_bytes().to_vec(),
+    };
+    if bytes.is_empty() {
+        return Err("The XOR key can't be empty.".to_owned());
+    }
+    Ok(bytes)
+}
+
+/// Replaces every byte according to a table.
+struct ByteMap {
+    table: [u8; 256],
+}
+impl ByteMap {
+    fn new(f: impl Fn(u8) -> u8) -> Self {
+        let mut table = [0; 256];
+        for (byte, mapped) in table.iter_mut().enumerate() {
+            *mapped = f(byte as u8);
+        }
+        Self { table }
+    }
+    /// Maps the ASCII letters by their position in the alphabet, keeping the case.
+    fn letters(f: impl Fn(u8) -> u8) -> Self {
+        Self::new(|b| match b {
+            b'a'..=b'z' => b'a' + f(b - b'a'),
+            b'A'..=b'Z' => b'A' + f(b - b'A'),
+            _ => b,
+        })
+    }
+}
+impl Transcoder for ByteMap {
+    fn update(&mut self, input: &[u8], _: u64, output: &mut Vec<u8>) -> Result<(), String> {
+        output.extend(input.iter().map(|b| self.table[*b as usize]));
+        Ok(())
+    }
+    fn finish(&mut self, _: u64, _: &mut Vec<u8>) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+struct Xor {
+    key: Vec<u8>,
+    position: usize,
+}
+impl Transcoder for Xor {
+    fn update(&mut self, input: &[u8], _: u64, output: &mut Vec<u8>) -> Result<(), String> {
+        for byte in input {
+            output.push(byte ^ self.key[self.position]);
+            self.position = (self.position + 1) % self.key.len();
+        }
+        Ok(())
+    }
+    fn finish(&mut self, _: u64, _: &mut Vec<u8>) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Reverses the whole input, by character if it's valid UTF-8 and otherwise by byte.
+/// A trailing newline of text is kept at the end.
+#[derive(Default)]
+struct Reverse {
+    buf: Vec<u8>,
+}
+impl Transcoder for Reverse {
+    fn update(&mut self, input: &[u8], _: u64, _: &mut Vec<u8>) -> Result<(), String> {
+        self.buf.extend_from_slice(input);
+        Ok(())
+    }
+    fn finish(&mut self, _: u64, output: &mut Vec<u8>) -> Result<(), String> {
+        match std::str::from_utf8(&self.buf) {
+            Ok(text) => {
+                let (text, newline) = match text.strip_suffix('\n') {
+                    Some(text) => (text, "\n"),
+                    None => (text, ""),
+                };
+                let mut reversed = String::with_capacity(text.len() + 1);
+                reversed.extend(text.chars().rev());
+                reversed.push_str(newline);
+                output.extend_from_slice(reversed.as_bytes());
+            }
+            Err(_) => output.extend(self.buf.iter().rev()),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+//! Percent-encoding of the components of URLs, and international domain names.
+
+use std::str::FromStr;
+
+use crate::codec::Transcoder;
+
+/// The part of a URL which is percent-encoded.
+/// The sets are those of the WHATWG URL standard, but `%` is always encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Component {
+    /// A value of any part, like `encodeURIComponent` in JavaScript.
+    Any,
+    Path,
+    /// A single segment of the path, where `/` is also encoded.
+    Segment,
+    Query,
+    Fragment,
+    Userinfo,
+    /// `application/x-www-form-urlencoded`, where space is `+`.
+    Form,
+}
+impl FromStr for Component {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "component" | "any" => Ok(Self::Any),
+            "path" => Ok(Self::Path),
+            "segment" => Ok(Self::Segment),
+            "query" => Ok(Self::Query),
+            "fragment" => Ok(Self::Fragment),
+            "userinfo" => Ok(Self::Userinfo),
+            "form" => Ok(Self::Form),
+            _ => Err(format!(
+                "Component '{}' is not valid. Available are: \
+                component, path, segment, query, fragment, userinfo and form.",
+                s
+            )),
+        }
+    }
+}
+impl Component {
+    /// The printable ASCII characters which are encoded, in addition to `%`.
+    fn reserved(&self) -> &'static [u8] {
+        match self {
+            Self::Fragment => b"\"<>`",
+            Self::Query => b"\"#<>'",
+            Self::Path => b"\"#<>?`{}^\\",
+            Self::Segment => b"\"#<>?`{}^\\/",
+            Self::Userinfo => b"\"#<>?`{}^\\/:;=@[]|",
+            Self::Any => b"\"#<>?`{}^\\/:;=@[]|$&+,",
+            Self::Form => b"\"#<>?`{}^\\/:;=@[]|$&+,!'()~",
+        }
+    }
+    /// Whether `byte` is written as it is, instead of percent-encoded.
+    pub(crate) fn keeps(&self, byte: u8) -> bool {
+        byte.is_ascii_graphic() && byte != b'%' && !self.reserved().contains(&byte)
+    }
+}
+
+pub struct UrlEncoder {
+    component: Component,
+}
+impl UrlEncoder {
+    pub fn new(component: Component) -> Self {
+        Self { component }
+    }
+}
+impl Transcoder for UrlEncoder {
+    fn update(&mut self, input: &[u8], _: u64, output: &mut Vec<u8>) -> Result<(), String> {
+        const HEX: &[u8; 16] = b"0123456789ABCDEF";
+        for byte in input {
+            if self.component.keeps(*byte) {
+                output.push(*byte);
+            } else if *byte == b' ' && self.component == Component::Form {
+                output.push(b'+');
+            } else {
+                output.extend_from_slice(&[
+                    b'%',
+                    HEX[(byte >> 4) as usize],
+                    HEX[(byte & 0xf) as usize],
+                ]);
+            }
+        }
+        Ok(())
+    }
+    fn finish(&mut self, _: u64, _: &mut Vec<u8>) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+pub struct UrlDecoder {
+    component: Component,
+    /// The hex digits after a `%`.
+    digits: Vec<u8>,
+    /// The offset of the last `%`.
+    percent: Option<u64>,
+}
+impl UrlDecoder {
+    pub fn new(component: Component) -> Self {
+        Self {
+            component,
+            digits: Vec::with_capacity(2),
+            percent: None,
+        }
+    }
+}
+impl Transcoder for UrlDecoder {
+    fn update(&mut self, input: &[u8], offset: u64, output: &mut Vec<u8>) -> Result<(), String> {
+        for (pos, byte) in input.iter().enumerate() {
+            if let Some(percent) = self.percent {
+                let digit = (*byte as char)
+                    .to_digit(16)
+                    .ok_or_else(|| format!("Invalid percent-encoding at byte {}.", percent))?;
+                self.digits.push(digit as u8);
+                if self.digits.len() == 2 {
+                    output.push(self.digits[0] << 4 | self.digits[1]);
+                    self.digits.clear();
+                    self.percent = None;
+                }
+                continue;
+            }
+            match byte {
+                b'%' => self.percent = Some(offset + pos as u64),
+                b'+' if self.component == Component::Form => output.push(b' '),
+                _ => output.push(*byte),
+            }
+        }
+        Ok(())
+    }
+    fn finish(&mut self, _: u64, _: &mut Vec<u8>) -> Result<(), String> {
+        match self.percent {
+            Some(percent) => Err(format!("Incomplete percent-encoding at byte {}.", percent)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Converts a label to or from Punycode (RFC 3492), without the `xn--` prefix.
+/// The prefix is ignored when decoding.
+pub fn punycode(label: &str, decode: bool) -> Result<String, String> {
+    if decode {
+        let raw = match label.get(..4) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("xn--") => &label[4..],
+            _ => label,
+        };
+        idna::punycode::decode_to_string(raw)
+            .ok_or_else(|| format!("'{}' is not valid Punycode.", label))
+    } else {
+        idna::punycode::encode_str(label)
+            .ok_or_else(|| format!("'{}' is too long to encode as Punycode.", label))
+    }
+}
+
+/// Converts an international domain name to its ASCII form (with `xn--` labels), or back.
+pub fn idna(domain: &str, decode: bool) -> Result<String, String> {
+    let invalid = || format!("'{}' is not a valid international domain name.", domain);
+    if decode {
+        let (unicode, result) = idna::domain_to_unicode(domain);
+        result.map(|()| unicode).map_err(|_| invalid())
+    } else {
+        idna::domain_to_ascii(domain).map_err(|_| invalid())
+    }
+}
@@ -189,7 +189,8 @@ impl Display for EscapeError {
             Self::InvalidCharacter => {
                 write!(
                     f,
-                    "the character you inputted (after \\x or \\u) isn't valid in UTF-8"
+                    "the character you inputted (after \\x or \\u) isn't valid in UTF-8, \
+                    or is above \\x7f after \\x"
                 )
             }
         }
@@ -211,6 +212,11 @@ impl Display for EscapeError {
 /// the input.
 /// [`EscapeError::InvalidEscape`] is returned is an invalid character was found after a `\`.
 pub fn parse_escaped(s: &str) -> Result<Cow<'_, str>, EscapeError> {
+    parse_escaped_at(s).map_err(|(_, err)| err)
+}
+/// Same as [`parse_escaped`], but the error also contains the byte index of the `\` which
+/// started the invalid escape.
+pub fn parse_escaped_at(s: &str) -> Result<Cow<'_, str>, (usize, EscapeError)> {
     if s.contains('\\') {
         let mut string = String::with_capacity(s.len());
         let mut skip = 0;
@@ -221,66 +227,72 @@ pub fn parse_escaped(s: &str) -> Result<Cow<'_, str>, EscapeError> {
             }
             if c == '\\' {
                 let s = &s[idx..];
-                let first_char = s.chars().nth(1).ok_or(EscapeError::InvalidEscape)?;
-                skip = match first_char {
-                    'n' => {
-                        string.push('\n');
-                        1
-                    }
-                    't' => {
-                        string.push('\t');
-                        1
-                    }
-                    'r' => {
-                        string.push('\r');
-                        1
-                    }
-                    '\\' => {
-                        string.push('\\');
-                        1
-                    }
-                    '0' => {
-                        string.push('\0');
-                        1
-                    }
-                    '\'' => {
-                        string.push('\'');
-                        1
-                    }
-                    '"' => {
-                        string.push('"');
-                        1
-                    }
-                    'x' => {
-                        let digits = s.get(2..=3).ok_or(EscapeError::InvalidCharacter)?;
-                        debug_assert_eq!(digits.as_bytes().len(), 2);
-                        let number = u32::from_str_radix(digits, 16)
-                            .map_err(|_| EscapeError::InvalidCharacter)?;
-                        let c = char::from_u32(number).ok_or(EscapeError::InvalidCharacter)?;
-                        string.push(c);
-                        3
-                    }
-                    'u' => {
-                        let closing = s.find('}');
-                        let closing = if let Some(c) = closing {
-                            c - 2 // the opening and `u` are two bytes
-                        } else {
-                            return Err(EscapeError::InvalidCharacter);
-                        };
-                        if s.as_bytes().get(2) != Some(&b'{') || closing > 6 {
-                            return Err(EscapeError::InvalidCharacter);
+                let mut resolve = || -> Result<usize, EscapeError> {
+                    let first_char = s.chars().nth(1).ok_or(EscapeError::InvalidEscape)?;
+                    Ok(match first_char {
+                        'n' => {
+                            string.push('\n');
+                            1
+                        }
+                        't' => {
+                            string.push('\t');
+                            1
+                        }
+                        'r' => {
+                            string.push('\r');
+                            1
+                        }
+                        '\\' => {
+                            string.push('\\');
+                            1
+                        }
+                        '0' => {
+                            string.push('\0');
+                            1
+                        }
+                        '\'' => {
+                            string.push('\'');
+                            1
+                        }
+                        '"' => {
+                            string.push('"');
+                            1
+                        }
+                        'x' => {
+                            let digits = s.get(2..=3).ok_or(EscapeError::InvalidCharacter)?;
+                            debug_assert_eq!(digits.len(), 2);
+                            let number = u32::from_str_radix(digits, 16)
+                                .map_err(|_| EscapeError::InvalidCharacter)?;
+                            // Like in Rust, only ASCII is allowed.
+                            let c = char::from_u32(number)
+                                .filter(char::is_ascii)
+                                .ok_or(EscapeError::InvalidCharacter)?;
+                            string.push(c);
+                            3
+                        }
+                        'u' => {
+                            let closing = s.find('}');
+                            let closing = if let Some(c) = closing {
+                                c - 2 // the opening and `u` are two bytes
+                            } else {
+                                return Err(EscapeError::InvalidCharacter);
+                            };
+                            if s.as_bytes().get(2) != Some(&b'{') || closing > 6 {
+                                return Err(EscapeError::InvalidCharacter);
+                            }
+                            // ok, since we found `closing` in slice.
+                            let digits = &s[3..closing + 2];
+                            let number = u32::from_str_radix(digits, 16)
+                                .map_err(|_| EscapeError::InvalidCharacter)?;
+                            let c = char::from_u32(number).ok_or(EscapeError::InvalidCharacter)?;
+                            string.push(c);
+                            2 + closing
                         }
-                        // ok, since we found `closing` in slice.
-                        let digits = &s[3..closing + 2];
-                        let number = u32::from_str_radix(digits, 16)
-                            .map_err(|_| EscapeError::InvalidCharacter)?;
-                        let c = char::from_u32(number).ok_or(EscapeError::InvalidCharacter)?;
-                        string.push(c);
-                        2 + closing
-                    }
 
-                    _ => return Err(EscapeError::InvalidEscape),
+                        _ => return Err(EscapeError::InvalidEscape),
+                    })
                 };
+                skip = resolve().map_err(|err| (idx, err))?;
             } else {
                 string.push(c)
             }
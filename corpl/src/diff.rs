@@ -0,0 +1,108 @@
+//! Unified diffs of the changes to a config file.
+
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::{get_lines, Change};
+
+/// A line and its line ending.
+type Line<'a> = (&'a [u8], &'a [u8]);
+
+/// The number of unchanged lines shown around changes.
+const CONTEXT: usize = 3;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const CYAN: &str = "\x1b[36m";
+
+/// Formats `change` as a unified diff, coloured with ANSI escapes if `color` is set.
+/// Returns an empty string if nothing changed.
+///
+/// corpl only ever rewrites lines, so line `n` of the original is compared to line `n`
+/// of the processed file. Lines are compared with their line endings, so a diff is shown
+/// whenever the files differ.
+pub fn unified(path: &Path, change: &Change, color: bool) -> String {
+    let original: Vec<Line> = get_lines(&change.original).with_endings().collect();
+    let processed: Vec<Line> = get_lines(&change.processed).with_endings().collect();
+    let len = original.len().max(processed.len());
+    let differs = |line: usize| original.get(line) != processed.get(line);
+    let changed: Vec<usize> = (0..len).filter(|line| differs(*line)).collect();
+    let mut diff = String::new();
+    if changed.is_empty() {
+        return diff;
+    }
+
+    let paint = |code: &'static str| if color { code } else { "" };
+    let reset = paint(RESET);
+    // UNWRAP (all `write!`s): writing to a String can't fail.
+    writeln!(diff, "{}--- {}{}", paint(BOLD), path.display(), reset).unwrap();
+    writeln!(diff, "{}+++ {}{}", paint(BOLD), path.display(), reset).unwrap();
+
+    let mut hunk_start = 0;
+    while hunk_start < changed.len() {
+        // Merge changes whose context would overlap into one hunk.
+        let mut hunk_end = hunk_start;
+        while hunk_end + 1 < changed.len()
+            && changed[hunk_end + 1] - changed[hunk_end] <= CONTEXT * 2 + 1
+        {
+            hunk_end += 1;
+        }
+        let start = changed[hunk_start].saturating_sub(CONTEXT);
+        let end = (changed[hunk_end] + CONTEXT + 1).min(len);
+        let count = |lines: &[Line]| lines.len().min(end).saturating_sub(start);
+        writeln!(
+            diff,
+            "{}@@ -{},{} +{},{} @@{}",
+            paint(CYAN),
+            start + 1,
+            count(&original),
+            start + 1,
+            count(&processed),
+            reset
+        )
+        .unwrap();
+
+        let mut line = start;
+        while line < end {
+            if !differs(line) {
+                write_line(&mut diff, " ", original[line], "", "");
+                line += 1;
+                continue;
+            }
+            // Write all removed lines of a run of changes before the added ones.
+            let run_end = (line..end).find(|line| !differs(*line)).unwrap_or(end);
+            for removed in original
+                .get(line..run_end.min(original.len()))
+                .unwrap_or(&[])
+            {
+                write_line(&mut diff, "-", *removed, paint(RED), reset);
+            }
+            for added in processed
+                .get(line..run_end.min(processed.len()))
+                .unwrap_or(&[])
+            {
+                write_line(&mut diff, "+", *added, paint(GREEN), reset);
+            }
+            line = run_end;
+        }
+        hunk_start = hunk_end + 1;
+    }
+    diff
+}
+
+/// Writes `line` after `prefix`. The line endings of changed lines are shown,
+/// `\r` as `^M` and a missing one as a note like the one of `diff`.
+fn write_line(diff: &mut String, prefix: &str, (line, ending): Line, color: &str, reset: &str) {
+    let line = String::from_utf8_lossy(line);
+    let cr = if prefix != " " && ending.starts_with(b"\r") {
+        "^M"
+    } else {
+        ""
+    };
+    writeln!(diff, "{}{}{}{}{}", color, prefix, line, cr, reset).unwrap();
+    if ending.is_empty() {
+        writeln!(diff, "\\ No newline at end of file").unwrap();
+    }
+}
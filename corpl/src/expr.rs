@@ -0,0 +1,163 @@
+//! Boolean expressions of `CORPL option` lines.
+//!
+//! Names are combined with `!`, `&&` and `||`, in order of precedence,
+//! and grouped with parentheses, such as `(dark || amoled) && !laptop`.
+//!
+//! A name can be ignored, which happens with `--keep` when it's neither enabled nor disabled.
+//! Ignored names are removed from the expression, and so is an operator if all its operands
+//! are removed. If the whole expression is removed, the lines of the option are left as they are.
+
+use std::fmt::{self, Display};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr<'a> {
+    Name(&'a [u8]),
+    Not(Box<Expr<'a>>),
+    And(Vec<Expr<'a>>),
+    Or(Vec<Expr<'a>>),
+}
+impl<'a> Expr<'a> {
+    /// Parses `bytes` as an expression.
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, SyntaxError> {
+        let mut parser = Parser { bytes, pos: 0 };
+        let expr = parser.or()?;
+        parser.skip_whitespace();
+        match parser.peek() {
+            None => Ok(expr),
+            Some(b')') => Err(parser.error("unmatched `)`")),
+            Some(_) => Err(parser.error("expected `&&` or `||`")),
+        }
+    }
+
+    /// The names in the expression, in order.
+    pub fn names(&self) -> Vec<&'a [u8]> {
+        match self {
+            Self::Name(name) => vec![name],
+            Self::Not(expr) => expr.names(),
+            Self::And(exprs) | Self::Or(exprs) => exprs.iter().flat_map(Self::names).collect(),
+        }
+    }
+
+    /// Evaluates the expression, where `status` returns if a name is enabled,
+    /// or [`None`] if it's ignored. Returns [`None`] if the whole expression is ignored.
+    pub fn evaluate(&self, status: &impl Fn(&[u8]) -> Option<bool>) -> Option<bool> {
+        match self {
+            Self::Name(name) => status(name),
+            Self::Not(expr) => expr.evaluate(status).map(|value| !value),
+            Self::And(exprs) => exprs
+                .iter()
+                .filter_map(|expr| expr.evaluate(status))
+                .reduce(|a, b| a && b),
+            Self::Or(exprs) => exprs
+                .iter()
+                .filter_map(|expr| expr.evaluate(status))
+                .reduce(|a, b| a || b),
+        }
+    }
+}
+
+/// An invalid expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    /// The byte offset in the expression.
+    pub offset: usize,
+    pub message: &'static str,
+}
+impl Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+    fn error(&self, message: &'static str) -> SyntaxError {
+        SyntaxError {
+            offset: self.pos,
+            message,
+        }
+    }
+    /// Consumes `operator` (`&&` or `||`) if it's next.
+    fn operator(&mut self, operator: &[u8]) -> Result<bool, SyntaxError> {
+        self.skip_whitespace();
+        let rest = &self.bytes[self.pos..];
+        if rest.starts_with(operator) {
+            self.pos += 2;
+            Ok(true)
+        } else if rest.first() == operator.first() {
+            Err(self.error(if operator == b"&&" {
+                "expected `&&`, found a single `&`"
+            } else {
+                "expected `||`, found a single `|`"
+            }))
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr<'a>, SyntaxError> {
+        let mut exprs = vec![self.and()?];
+        while self.operator(b"||")? {
+            exprs.push(self.and()?);
+        }
+        Ok(if exprs.len() == 1 {
+            exprs.pop().unwrap()
+        } else {
+            Expr::Or(exprs)
+        })
+    }
+    fn and(&mut self) -> Result<Expr<'a>, SyntaxError> {
+        let mut exprs = vec![self.unary()?];
+        while self.operator(b"&&")? {
+            exprs.push(self.unary()?);
+        }
+        Ok(if exprs.len() == 1 {
+            exprs.pop().unwrap()
+        } else {
+            Expr::And(exprs)
+        })
+    }
+    fn unary(&mut self) -> Result<Expr<'a>, SyntaxError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'!') => {
+                self.pos += 1;
+                Ok(Expr::Not(Box::new(self.unary()?)))
+            }
+            Some(b'(') => {
+                self.pos += 1;
+                let expr = self.or()?;
+                self.skip_whitespace();
+                if self.peek() != Some(b')') {
+                    return Err(self.error("expected `)`"));
+                }
+                self.pos += 1;
+                Ok(expr)
+            }
+            _ => {
+                let start = self.pos;
+                while self
+                    .peek()
+                    .is_some_and(|b| !b.is_ascii_whitespace() && !b"!()&|".contains(&b))
+                {
+                    self.pos += 1;
+                }
+                if start == self.pos {
+                    return Err(self.error("expected a name, `!` or `(`"));
+                }
+                Ok(Expr::Name(&self.bytes[start..self.pos]))
+            }
+        }
+    }
+}
@@ -1,11 +1,17 @@
 use std::borrow::Cow;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::{
-    fs::OpenOptions,
-    io::{Read, Seek, SeekFrom, Write},
-    path::Path,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
 };
 
+pub mod diff;
+pub mod expr;
+pub mod lint;
+pub mod profile;
+pub mod value;
+
 #[derive(Debug, Clone, Copy)]
 pub struct Comment<'a> {
     open: &'a [u8],
@@ -23,6 +29,17 @@ impl<'a> Comment<'a> {
     }
 }
 
+/// Which names to enable and disable, and the values to set.
+#[derive(Debug, Clone, Default)]
+pub struct Names<'a> {
+    pub enabled: HashSet<&'a [u8]>,
+    pub disabled: HashSet<&'a [u8]>,
+    /// Leave options and sections with names neither enabled nor disabled as they are.
+    pub keep: bool,
+    /// The values of `CORPL value` lines.
+    pub values: HashMap<&'a [u8], &'a [u8]>,
+}
+
 #[derive(Debug, PartialEq, Eq)]
 enum OptionEnabled {
     Yes,
@@ -36,191 +53,340 @@ enum Segment<'a> {
     None,
 }
 
+/// The contents of a config file before and after processing.
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub original: Vec<u8>,
+    pub processed: Vec<u8>,
+}
+impl Change {
+    pub fn is_changed(&self) -> bool {
+        self.original != self.processed
+    }
+}
+
+/// What [`process_file`] does with the processed config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteMode<'a> {
+    /// Leave the file untouched.
+    DryRun,
+    /// Atomically replace the file if it changed.
+    Replace {
+        /// If set, the original is first copied to its path with this suffix appended.
+        backup_suffix: Option<&'a str>,
+    },
+}
+
+/// Processes the config file at `path` and writes the result back according to `mode`.
 pub fn process_file(
     path: &Path,
     comment: Option<Comment>,
-    enabled: &HashSet<&[u8]>,
-    disabled: &HashSet<&[u8]>,
-    keep: bool,
+    names: &Names,
     max_comment_len: Option<usize>,
-) -> Result<(), Cow<'static, str>> {
-    let get_status = |option: &[u8]| {
-        if keep {
-            if disabled.contains(option) {
-                Some(false)
-            } else if enabled.contains(option) {
-                Some(true)
-            } else {
-                None
-            }
-        } else {
-            Some(enabled.contains(option))
-        }
-    };
-
-    let mut file = match OpenOptions::new().read(true).write(true).open(path) {
-        Ok(f) => f,
+    mode: WriteMode,
+) -> Result<Change, Cow<'static, str>> {
+    let config = match fs::read(path) {
+        Ok(c) => c,
         Err(_) => {
             return Err(Cow::Borrowed(
-                "Failed to open config file. Check input path.",
+                "Failed to read config file. Check input path.",
             ))
         }
     };
-    let mut config = Vec::with_capacity(4096);
-    if file.read_to_end(&mut config).is_err() {
-        return Err(Cow::Borrowed("Failed to read file."));
+    let output = process_config(&config, path, comment, names, max_comment_len)?;
+    let change = Change {
+        original: config,
+        processed: output,
     };
-    let line_ending = get_line_ending(&config);
-    let mut lines = get_lines(&config).peekable();
-    fn get_common_comments(bytes: &[u8]) -> Option<&'static [u8]> {
-        if bytes.starts_with(b"#") {
-            Some(b"#")
-        } else if bytes.starts_with(b"//") {
-            Some(b"//")
-        } else if bytes.starts_with(b";") {
-            Some(b";")
-        } else {
-            None
+    match mode {
+        WriteMode::Replace { backup_suffix } if change.is_changed() => {
+            replace_file(path, &change.processed, backup_suffix)?;
         }
+        _ => {}
     }
-    let end_comment = comment.as_ref().and_then(Comment::close);
-    let comment = match get_common_comments(&config).or_else(|| comment.as_ref().map(Comment::open))
-    {
-        Some(c) => c,
-        None => {
-            let first_line = match lines.peek() {
-                Some(l) => *l,
-                None => {
-                    return Err(Cow::Borrowed(
-                        "File too short; could not determine comment character.",
-                    ))
-                }
-            };
-            if max_comment_len.is_none()
-                || first_line
-                    .split(|b| is_whitespace(*b))
-                    .next()
-                    .unwrap()
-                    .len()
-                    <= max_comment_len.unwrap()
-            {
-                let comment = first_line.split(|b| b == &32).next().unwrap();
-                eprintln!(
-                    "Continuing with uncommon comment: '{}'",
-                    String::from_utf8_lossy(comment)
-                );
-                comment
+    Ok(change)
+}
+
+/// Replaces the file at `path` with `contents` by writing a temporary file next to it
+/// and renaming it over the original, so a crash never leaves a half-written file.
+///
+/// Symlinks are followed, and the permissions and (if allowed) ownership are kept.
+fn replace_file(
+    path: &Path,
+    contents: &[u8],
+    backup_suffix: Option<&str>,
+) -> Result<(), Cow<'static, str>> {
+    let target = fs::canonicalize(path)
+        .map_err(|_| Cow::Borrowed("Failed to resolve the path of the config file."))?;
+    let metadata = fs::metadata(&target)
+        .map_err(|_| Cow::Borrowed("Failed to get the metadata of the config file."))?;
+    // UNWRAP: a canonical path to a file always has a parent and a file name.
+    let dir = target.parent().unwrap();
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(target.file_name().unwrap());
+    temp_name.push(format!(".corpl-{}.tmp", std::process::id()));
+    let temp = dir.join(temp_name);
+
+    let write = || -> Result<(), Cow<'static, str>> {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp)
+            .map_err(|_| Cow::Borrowed("Failed to create a temporary file next to the config."))?;
+        file.write_all(contents)
+            .map_err(|_| Cow::Borrowed("Failed to write to the temporary file."))?;
+        file.set_permissions(metadata.permissions())
+            .map_err(|_| Cow::Borrowed("Failed to set the permissions of the temporary file."))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{fchown, MetadataExt};
+            // Only root can give the file away, but the group can often be kept.
+            if fchown(&file, Some(metadata.uid()), Some(metadata.gid())).is_err() {
+                let _ = fchown(&file, None, Some(metadata.gid()));
+            }
+        }
+        file.sync_all()
+            .map_err(|_| Cow::Borrowed("Failed to flush the temporary file to disk."))?;
+        if let Some(suffix) = backup_suffix {
+            let mut backup = target.clone().into_os_string();
+            backup.push(suffix);
+            fs::copy(&target, PathBuf::from(backup))
+                .map_err(|_| Cow::Borrowed("Failed to write the backup file."))?;
+        }
+        fs::rename(&temp, &target)
+            .map_err(|_| Cow::Borrowed("Failed to replace the config file."))?;
+        Ok(())
+    };
+    if let Err(err) = write() {
+        let _ = fs::remove_file(&temp);
+        return Err(err);
+    }
+    // Make the rename durable. Not all platforms can sync directories.
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+/// Processes the contents of a config file. `path` is used to detect the comment string
+/// and in error messages.
+pub fn process_config(
+    config: &[u8],
+    path: &Path,
+    comment: Option<Comment>,
+    names: &Names,
+    max_comment_len: Option<usize>,
+) -> Result<Vec<u8>, Cow<'static, str>> {
+    let get_status = |option: &[u8]| {
+        if names.keep {
+            if names.disabled.contains(option) {
+                Some(false)
+            } else if names.enabled.contains(option) {
+                Some(true)
             } else {
-                return Err(Cow::Owned(format!("Failed to get comment string in {}. Please enter it, and only it, as the first line or supply the `-c` option with the comment string.", path.display())));
+                None
             }
+        } else {
+            Some(names.enabled.contains(option))
         }
     };
 
+    let comment = detect_comment(config, path, comment, max_comment_len)?;
+    let end_comment = comment.close;
+    let comment = comment.open;
+    // Every line is written with its own line ending, which is empty for the last line
+    // if the file doesn't end with one.
+    let lines = get_lines(config).with_endings();
+
     let mut state = Segment::None;
     let mut output = Vec::with_capacity(config.len() * 2);
+    // The target and value of the previous `CORPL value` line.
+    let mut pending_value: Option<(value::Target, &[u8])> = None;
+    // The name of the current `CORPL select` and its value, if it's set.
+    let mut select: Option<(&[u8], Option<&[u8]>)> = None;
 
-    for line in lines {
-        let line_trimmed = trim(line);
-        #[allow(clippy::int_plus_one)]
-        if line_trimmed.len() >= comment.len() + 1 + 5 + 1
-            && line_trimmed.starts_with(comment)
-            && line_trimmed[comment.len()..].starts_with(b" CORPL ")
-        {
-            let start = comment.len() + 1 + 5 + 1;
-
-            let is_end = match end_comment.as_ref() {
-                None => &line_trimmed[start..] == b"end",
-                Some(end_comment) => {
-                    &line_trimmed[start..start + 4] == b"end "
-                        && &line_trimmed[start + 4..] == *end_comment
+    for (index, (line, line_ending)) in lines.enumerate() {
+        let marker = parse_marker(line, comment, end_comment);
+        let substituted;
+        let line = match pending_value.take() {
+            None => line,
+            Some((target, value)) => match target.find(line) {
+                Some(range) if marker.is_none() => {
+                    substituted = [&line[..range.start], value, &line[range.end..]].concat();
+                    &substituted[..]
                 }
-            };
-
-            if is_end {
-                state = Segment::None;
-            } else if line_trimmed[start..].starts_with(b"section ") {
-                let trimmed_start = first_non_whitespace(line);
-                let start = trimmed_start + start + 8;
-
-                let sec_str = &line[start..];
-
-                if sec_str.is_empty() {
-                    eprintln!("Found a section with no replacement! Does no lines have anything in common, then append it to the section line and remove it from all the following.");
+                _ => {
+                    eprintln!(
+                        "The target of the value on line {} is not present! Ignoring line.",
+                        index + 1
+                    );
+                    line
                 }
-
-                state = Segment::Section(sec_str);
-                if end_comment.is_some() {
-                    eprintln!("End comment is not compatible with sections for now.");
+            },
+        };
+        let line_trimmed = trim(line);
+        if let Some(marker) = marker {
+            match marker {
+                Marker::Value(name, target, offset) => {
+                    let target = value::Target::parse(target).map_err(|err| {
+                        format!("{}:{}:{}: {}.", path.display(), index + 1, offset + 1, err)
+                    })?;
+                    if let Some(value) = names.values.get(name) {
+                        if value.contains(&b'\n') || value.contains(&b'\r') {
+                            return Err(Cow::Owned(format!(
+                                "The value of {} contains a line break.",
+                                String::from_utf8_lossy(name)
+                            )));
+                        }
+                        pending_value = Some((target, value));
+                    }
+                }
+                Marker::End => {
                     state = Segment::None;
+                    select = None;
                 }
-            } else if line_trimmed[start..].starts_with(b"option ") {
-                let start = start + 7;
-                let end = match end_comment.as_ref() {
-                    Some(c) => line_trimmed.len() - c.len() - 1,
-                    None => line_trimmed.len(),
-                };
-                let option = &line_trimmed[start..end];
-                let options = common::slice_split(option, b" && ");
-                let mut option_enabled = OptionEnabled::Ignore;
-                for option in options {
-                    let trimmed_option = option.strip_prefix(b"!").unwrap_or(option);
-                    let contains = get_status(trimmed_option);
-                    let negate = option.starts_with(b"!");
-                    let Some(contains) = contains else { continue };
-
-                    if option_enabled == OptionEnabled::Ignore {
-                        option_enabled = OptionEnabled::Yes;
+                Marker::Select(name) => {
+                    let value = names.values.get(name).copied();
+                    if let Some(value) = value {
+                        let cases = select_cases(config, index, comment, end_comment);
+                        if !cases.contains(&value) {
+                            let cases: Vec<_> = cases
+                                .iter()
+                                .map(|case| String::from_utf8_lossy(case))
+                                .collect();
+                            return Err(Cow::Owned(format!(
+                                "{}:{}: '{}' is not a case of {}. Available are: {}.",
+                                path.display(),
+                                index + 1,
+                                String::from_utf8_lossy(value),
+                                String::from_utf8_lossy(name),
+                                cases.join(", ")
+                            )));
+                        }
                     }
-                    if (negate && contains) || (!negate && !contains) {
-                        option_enabled = OptionEnabled::No;
-                        break;
+                    select = Some((name, value));
+                    state = Segment::None;
+                }
+                Marker::Case(case) => {
+                    state = match select {
+                        Some((_, Some(value))) if value == case => {
+                            Segment::Option(OptionEnabled::Yes)
+                        }
+                        Some((_, Some(_))) => Segment::Option(OptionEnabled::No),
+                        Some((_, None)) => Segment::Option(OptionEnabled::Ignore),
+                        None => {
+                            eprintln!("Found a case outside of a select! Ignoring it.");
+                            Segment::None
+                        }
+                    };
+                }
+                Marker::Section(sec_str) => {
+                    if sec_str.is_empty() {
+                        eprintln!("Found a section with no replacement! Does no lines have anything in common, then append it to the section line and remove it from all the following.");
                     }
+
+                    state = Segment::Section(sec_str);
+                }
+                Marker::Option(option, offset) => {
+                    let expr = expr::Expr::parse(option).map_err(|err| {
+                        format!(
+                            "{}:{}:{}: {}.",
+                            path.display(),
+                            index + 1,
+                            offset + err.offset + 1,
+                            err
+                        )
+                    })?;
+                    let option_enabled = match expr.evaluate(&get_status) {
+                        Some(true) => OptionEnabled::Yes,
+                        Some(false) => OptionEnabled::No,
+                        None => OptionEnabled::Ignore,
+                    };
+                    state = Segment::Option(option_enabled);
                 }
-                state = Segment::Option(option_enabled);
+                Marker::Unknown => {}
             }
         } else {
             match state {
                 Segment::Section(seg_str) if !line_trimmed.is_empty() => {
-                    if end_comment.is_none() {
-                        let last = get_last(line, comment);
-                        let activate = last.map_or(Some(false), |last| get_status(last));
-                        let Some(activate) = activate else {
-                            // return early
-                            output.extend_from_slice(line);
+                    let name = section_name(line, comment, end_comment);
+                    let activate = name.map_or(Some(false), |(_, name)| get_status(name));
+                    let Some(activate) = activate else {
+                        // return early
+                        output.extend_from_slice(line);
+                        output.extend_from_slice(line_ending);
+                        continue;
+                    };
+                    let start = first_non_whitespace(line);
+                    let currently_active = !is_commented(line, comment);
+
+                    if currently_active == activate {
+                        // Do noting!
+                    } else if !activate {
+                        if line[start..].starts_with(seg_str) {
+                            // Do stuff
+                            output.extend_from_slice(&line[..start]);
+                            output.extend_from_slice(comment);
+                            output.push(32);
+                            let content_start = start + seg_str.len();
+                            match end_comment {
+                                None => output.extend_from_slice(&line[content_start..]),
+                                Some(end_comment) => {
+                                    // Close the comment before the name, keeping the whitespace.
+                                    let name_start =
+                                        name.map_or(line.len(), |(pos, _)| pos.max(content_start));
+                                    let content = &line[content_start..name_start];
+                                    let content_end = last_non_whitespace(content);
+                                    escape_comment(
+                                        &content[..content_end],
+                                        end_comment,
+                                        &mut output,
+                                    );
+                                    output.push(32);
+                                    output.extend_from_slice(end_comment);
+                                    output.extend_from_slice(&line[content_start + content_end..]);
+                                }
+                            }
                             output.extend_from_slice(line_ending);
                             continue;
-                        };
-                        let start = first_non_whitespace(line);
-                        let currently_active = !(line[start..].starts_with(comment)
-                            && line.get(start + comment.len()).copied() == Some(32));
-
-                        if currently_active == activate {
-                            // Do noting!
-                        } else if !activate {
-                            if line[start..].starts_with(seg_str) {
-                                // Do stuff
+                        } else {
+                            eprintln!("Common string of section not present! Ignoring line.")
+                        }
+                    } else if activate {
+                        let content_start = start + comment.len() + 1;
+                        match end_comment {
+                            None => {
                                 output.extend_from_slice(&line[..start]);
-                                output.extend_from_slice(comment);
-                                output.push(32);
-                                output.extend_from_slice(&line[start + seg_str.len()..]);
+                                output.extend_from_slice(seg_str);
+                                output.extend_from_slice(&line[content_start..]);
                                 output.extend_from_slice(line_ending);
                                 continue;
-                            } else {
-                                eprintln!("Common string of section not present! Ignoring line.")
                             }
-                        } else if activate {
-                            output.extend_from_slice(&line[..start]);
-                            output.extend_from_slice(seg_str);
-                            output.extend_from_slice(&line[start + comment.len() + 1..]);
-                            output.extend_from_slice(line_ending);
-                            continue;
+                            Some(end_comment) => {
+                                // `activate` is only true if the line has a name.
+                                let name_start = name.map_or(line.len(), |(pos, _)| pos);
+                                let close = find(&line[content_start..], end_comment)
+                                    .map(|pos| content_start + pos)
+                                    .filter(|close| *close < name_start);
+                                if let Some(close) = close {
+                                    let content = &line[content_start..close];
+                                    let content = content.strip_suffix(b" ").unwrap_or(content);
+                                    output.extend_from_slice(&line[..start]);
+                                    output.extend_from_slice(seg_str);
+                                    unescape_comment(content, end_comment, &mut output);
+                                    output.extend_from_slice(&line[close + end_comment.len()..]);
+                                    output.extend_from_slice(line_ending);
+                                    continue;
+                                } else {
+                                    eprintln!("Closing comment of section line not present! Ignoring line.")
+                                }
+                            }
                         }
                     }
                 }
                 Segment::Option(ref enabled) if !line_trimmed.is_empty() => {
                     let start = first_non_whitespace(line);
-                    let currently_active =
-                        !(line[start..].starts_with(comment) && line[start + comment.len()] == 32);
+                    let currently_active = !is_commented(line, comment);
 
                     match enabled {
                         OptionEnabled::Ignore => {
@@ -236,32 +402,41 @@ pub fn process_file(
                             output.extend_from_slice(&line[..start]);
                             output.extend_from_slice(comment);
                             output.push(32);
-                            output.extend_from_slice(&line[start..]);
-                            if let Some(comment) = end_comment {
-                                output.push(32);
-                                output.extend_from_slice(comment);
-                            }
-                            output.extend_from_slice(line_ending);
-                            continue;
-                        }
-                        OptionEnabled::Yes => {
-                            output.extend_from_slice(&line[..start]);
                             match end_comment {
-                                None => {
-                                    output.extend_from_slice(&line[start + comment.len() + 1..])
-                                }
+                                None => output.extend_from_slice(&line[start..]),
                                 Some(end_comment) => {
-                                    let end = last_non_whitespace(line);
-                                    output.extend_from_slice(
-                                        &line[start + comment.len() + 1
-                                            ..end - end_comment.len() - 1],
-                                    );
-                                    output.extend_from_slice(&line[end..]);
+                                    escape_comment(&line[start..], end_comment, &mut output);
+                                    output.push(32);
+                                    output.extend_from_slice(end_comment);
                                 }
                             }
                             output.extend_from_slice(line_ending);
                             continue;
                         }
+                        OptionEnabled::Yes => match end_comment {
+                            None => {
+                                output.extend_from_slice(&line[..start]);
+                                output.extend_from_slice(&line[start + comment.len() + 1..]);
+                                output.extend_from_slice(line_ending);
+                                continue;
+                            }
+                            Some(end_comment) => {
+                                let end = last_non_whitespace(line);
+                                let content = line
+                                    .get(start + comment.len() + 1..end)
+                                    .and_then(|content| content.strip_suffix(end_comment));
+                                if let Some(content) = content {
+                                    let content = content.strip_suffix(b" ").unwrap_or(content);
+                                    output.extend_from_slice(&line[..start]);
+                                    unescape_comment(content, end_comment, &mut output);
+                                    output.extend_from_slice(&line[end..]);
+                                    output.extend_from_slice(line_ending);
+                                    continue;
+                                } else {
+                                    eprintln!("Closing comment of option line not present! Ignoring line.")
+                                }
+                            }
+                        },
                     }
                 }
                 _ => {
@@ -273,18 +448,401 @@ pub fn process_file(
         // Newline character
         output.extend_from_slice(line_ending);
     }
-    match file.set_len(output.len() as u64) {
-        Ok(_) => {}
-        Err(_) => return Err(Cow::Borrowed("Failed to set file length.")),
+    Ok(output)
+}
+
+/// Finds the comment string of `config`, in order of priority:
+/// `comment`, a `corpl: comment=` modeline, the interpreter of a shebang, a common one at
+/// the start of the file, the extension of `path`, and else the first word of the first line.
+fn detect_comment<'a>(
+    config: &'a [u8],
+    path: &Path,
+    comment: Option<Comment<'a>>,
+    max_comment_len: Option<usize>,
+) -> Result<Comment<'a>, Cow<'static, str>> {
+    fn get_common_comments(bytes: &[u8]) -> Option<&'static [u8]> {
+        if bytes.starts_with(b"#") {
+            Some(b"#")
+        } else if bytes.starts_with(b"//") {
+            Some(b"//")
+        } else if bytes.starts_with(b";") {
+            Some(b";")
+        } else {
+            None
+        }
+    }
+    let line_comment = |open| Comment { open, close: None };
+    if let Some(comment) = comment
+        .or_else(|| comment_by_modeline(config))
+        .or_else(|| comment_by_shebang(config))
+        .or_else(|| get_common_comments(config).map(line_comment))
+        .or_else(|| comment_by_extension(path))
+    {
+        return Ok(comment);
+    }
+    let first_line = match get_lines(config).next() {
+        Some(l) => l,
+        None => {
+            return Err(Cow::Borrowed(
+                "File too short; could not determine comment character.",
+            ))
+        }
+    };
+    if max_comment_len.is_none()
+        || first_line
+            .split(|b| is_whitespace(*b))
+            .next()
+            .unwrap()
+            .len()
+            <= max_comment_len.unwrap()
+    {
+        let comment = first_line.split(|b| b == &32).next().unwrap();
+        eprintln!(
+            "Continuing with uncommon comment: '{}'",
+            String::from_utf8_lossy(comment)
+        );
+        Ok(line_comment(comment))
+    } else {
+        Err(Cow::Owned(format!("Failed to get comment string in {}. Please enter it, and only it, as the first line or supply the `-c` option with the comment string.", path.display())))
+    }
+}
+
+/// The number of lines at the start and end of a file searched for a modeline.
+const MODELINES: usize = 5;
+
+/// Finds a `corpl: comment=OPEN [close=CLOSE]` modeline in the first or last lines of `config`.
+fn comment_by_modeline(config: &[u8]) -> Option<Comment<'_>> {
+    let lines: Vec<&[u8]> = get_lines(config).collect();
+    let end = lines
+        .len()
+        .saturating_sub(MODELINES)
+        .max(MODELINES.min(lines.len()));
+    let candidates = lines[..MODELINES.min(lines.len())]
+        .iter()
+        .chain(&lines[end..]);
+    for line in candidates {
+        let Some(pos) = find(line, b"corpl: ") else {
+            continue;
+        };
+        let mut comment = None;
+        let mut close = None;
+        for word in line[pos + 7..].split(|b| is_whitespace(*b)) {
+            if let Some(open) = word.strip_prefix(b"comment=".as_ref()) {
+                comment = Some(open).filter(|open| !open.is_empty());
+            } else if let Some(word) = word.strip_prefix(b"close=".as_ref()) {
+                close = Some(word).filter(|word| !word.is_empty());
+            }
+        }
+        if let Some(open) = comment {
+            return Some(Comment { open, close });
+        }
+    }
+    None
+}
+
+/// Gets the comment string of the interpreter in a `#!` line.
+fn comment_by_shebang(config: &[u8]) -> Option<Comment<'static>> {
+    let shebang = get_lines(config).next()?.strip_prefix(b"#!")?;
+    let mut words = shebang
+        .split(|b| is_whitespace(*b))
+        .filter(|word| !word.is_empty());
+    let mut interpreter = words.next()?.rsplit(|b| *b == b'/').next()?;
+    if interpreter == b"env" {
+        interpreter = words.find(|word| !word.starts_with(b"-"))?;
+    }
+    let open: &[u8] = match interpreter {
+        b"lua" | b"luajit" => b"--",
+        b"node" | b"deno" => b"//",
+        // Shells, Python, Ruby, Perl and most other interpreters.
+        _ => b"#",
+    };
+    Some(Comment { open, close: None })
+}
+
+/// Gets the comment syntax of the extension of `path`.
+fn comment_by_extension(path: &Path) -> Option<Comment<'static>> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    let (open, close): (&[u8], Option<&[u8]>) = match extension.as_str() {
+        "lua" | "sql" => (b"--", None),
+        "vim" => (b"\"", None),
+        "css" => (b"/*", Some(b"*/")),
+        "html" | "htm" | "xml" | "svg" => (b"<!--", Some(b"-->")),
+        "tex" | "sty" => (b"%", None),
+        "ini" => (b";", None),
+        "conf" | "toml" | "yaml" | "yml" | "sh" | "py" => (b"#", None),
+        "js" | "jsonc" | "rs" | "c" | "h" | "cpp" => (b"//", None),
+        _ => return None,
     };
-    match file.seek(SeekFrom::Start(0)) {
-        Ok(_) => {}
-        Err(_) => return Err(Cow::Borrowed("Failed to seek in file.")),
+    Some(Comment { open, close })
+}
+
+/// A `CORPL` line.
+enum Marker<'a> {
+    End,
+    /// The common string of the lines of the section.
+    Section(&'a [u8]),
+    /// The expression of the option, and its byte offset in the line.
+    Option(&'a [u8], usize),
+    /// The name of a value which selects one of the following cases.
+    Select(&'a [u8]),
+    /// The value of the select for which the following lines are active.
+    Case(&'a [u8]),
+    /// The name and target of a value, and the byte offset of the target in the line.
+    Value(&'a [u8], &'a [u8], usize),
+    /// A `CORPL` line this version doesn't understand. It's left as it is.
+    Unknown,
+}
+
+/// Parses `line` as a `CORPL` line. Returns [`None`] if it isn't one.
+fn parse_marker<'a>(
+    line: &'a [u8],
+    comment: &[u8],
+    end_comment: Option<&[u8]>,
+) -> Option<Marker<'a>> {
+    let rest = trim(line)
+        .strip_prefix(comment)?
+        .strip_prefix(b" CORPL ".as_ref())?;
+
+    let is_end = match end_comment {
+        None => rest == b"end",
+        Some(end_comment) => rest
+            .strip_prefix(b"end ".as_ref())
+            .is_some_and(|rest| rest == end_comment),
+    };
+    let argument = |argument: &'a [u8]| match end_comment {
+        Some(end_comment) => trim_end_comment(argument, end_comment),
+        None => argument,
+    };
+    if is_end {
+        Some(Marker::End)
+    } else if rest.starts_with(b"section ") {
+        // The common string keeps any trailing whitespace, also before a closing comment.
+        let start = first_non_whitespace(line) + comment.len() + 7 + 8;
+        let common = &line[start..];
+        let common = match end_comment {
+            Some(end_comment) => common[..last_non_whitespace(common)]
+                .strip_suffix(end_comment)
+                .unwrap_or(common),
+            None => common,
+        };
+        Some(Marker::Section(common))
+    } else if let Some(option) = rest.strip_prefix(b"option ".as_ref()) {
+        let option = argument(option);
+        let offset = option.as_ptr() as usize - line.as_ptr() as usize;
+        Some(Marker::Option(option, offset))
+    } else if let Some(value) = rest.strip_prefix(b"value ".as_ref()) {
+        let value = trim(argument(value));
+        let name_len = value
+            .iter()
+            .position(|b| is_whitespace(*b))
+            .unwrap_or(value.len());
+        let target = trim(&value[name_len..]);
+        let offset = target.as_ptr() as usize - line.as_ptr() as usize;
+        Some(Marker::Value(&value[..name_len], target, offset))
+    } else if let Some(name) = rest.strip_prefix(b"select ".as_ref()) {
+        Some(Marker::Select(trim(argument(name))))
+    } else if let Some(name) = rest.strip_prefix(b"case ".as_ref()) {
+        Some(Marker::Case(trim(argument(name))))
+    } else {
+        Some(Marker::Unknown)
+    }
+}
+
+/// The cases of the `CORPL select` on line `index` of `config`, up to its end.
+fn select_cases<'a>(
+    config: &'a [u8],
+    index: usize,
+    comment: &[u8],
+    end_comment: Option<&[u8]>,
+) -> Vec<&'a [u8]> {
+    get_lines(config)
+        .skip(index + 1)
+        .map(|line| parse_marker(line, comment, end_comment))
+        .take_while(|marker| !matches!(marker, Some(Marker::End)))
+        .filter_map(|marker| match marker {
+            Some(Marker::Case(case)) => Some(case),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Removes `end_comment` and the space before it from the end of `bytes`, after any whitespace.
+fn trim_end_comment<'a>(bytes: &'a [u8], end_comment: &[u8]) -> &'a [u8] {
+    bytes[..last_non_whitespace(bytes)]
+        .strip_suffix(end_comment)
+        .and_then(|bytes| bytes.strip_suffix(b" ".as_ref()))
+        .unwrap_or(bytes)
+}
+
+/// Whether `line` starts with `comment` and a space, after any indentation.
+fn is_commented(line: &[u8], comment: &[u8]) -> bool {
+    let start = first_non_whitespace(line);
+    line[start..].starts_with(comment) && line.get(start + comment.len()) == Some(&32)
+}
+
+/// Whether the lines controlled by a name are commented out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    Active,
+    Commented,
+    /// Some lines are active and some are commented out.
+    Mixed,
+}
+impl State {
+    fn merge(state: Option<Self>, active: bool) -> Self {
+        let line = if active {
+            Self::Active
+        } else {
+            Self::Commented
+        };
+        match state {
+            Some(state) if state != line => Self::Mixed,
+            _ => line,
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Commented => "commented",
+            Self::Mixed => "mixed",
+        }
     }
-    match file.write_all(&output[..]) {
-        Ok(_) => Ok(()),
-        Err(_) => return Err(Cow::Borrowed("Failed to write to file.")),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    Section,
+    Option,
+    /// A case of a select, named `SELECT=CASE`.
+    Case,
+}
+
+/// A section or option name, or a case of a select, in a config file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub kind: EntryKind,
+    /// The expression of an option, or the name of lines in a section.
+    pub name: String,
+    /// The first and last line, starting at 1, of the lines controlled by the name.
+    pub lines: (usize, usize),
+    pub state: State,
+}
+
+/// Lists the options and the names in sections of the config file at `path`.
+pub fn list_file(
+    path: &Path,
+    comment: Option<Comment>,
+    max_comment_len: Option<usize>,
+) -> Result<Vec<Entry>, Cow<'static, str>> {
+    let config = match fs::read(path) {
+        Ok(c) => c,
+        Err(_) => {
+            return Err(Cow::Borrowed(
+                "Failed to read config file. Check input path.",
+            ))
+        }
+    };
+    list_config(&config, path, comment, max_comment_len)
+}
+
+/// Lists the options and the names in sections of `config`, in the order they first appear.
+/// `path` is used to detect the comment string and in error messages.
+pub fn list_config(
+    config: &[u8],
+    path: &Path,
+    comment: Option<Comment>,
+    max_comment_len: Option<usize>,
+) -> Result<Vec<Entry>, Cow<'static, str>> {
+    let comment = detect_comment(config, path, comment, max_comment_len)?;
+    let end_comment = comment.close;
+    let comment = comment.open;
+
+    let mut entries: Vec<Entry> = Vec::new();
+    // The index of the entry of the current option, or the start of the entries of the section.
+    let mut current: Option<(EntryKind, usize)> = None;
+    let mut select = None;
+    for (index, line) in get_lines(config).enumerate() {
+        let number = index + 1;
+        if let Some(marker) = parse_marker(line, comment, end_comment) {
+            current = match marker {
+                Marker::End => {
+                    select = None;
+                    None
+                }
+                Marker::Select(name) => {
+                    select = Some(name);
+                    None
+                }
+                Marker::Case(case) => {
+                    let Some(select) = select else {
+                        current = None;
+                        continue;
+                    };
+                    entries.push(Entry {
+                        kind: EntryKind::Case,
+                        name: format!(
+                            "{}={}",
+                            String::from_utf8_lossy(select),
+                            String::from_utf8_lossy(case)
+                        ),
+                        lines: (0, 0),
+                        state: State::Active,
+                    });
+                    Some((EntryKind::Case, entries.len() - 1))
+                }
+                Marker::Section(_) => Some((EntryKind::Section, entries.len())),
+                Marker::Option(option, _) => {
+                    entries.push(Entry {
+                        kind: EntryKind::Option,
+                        name: String::from_utf8_lossy(option).into_owned(),
+                        lines: (0, 0),
+                        // Changed by the first line.
+                        state: State::Active,
+                    });
+                    Some((EntryKind::Option, entries.len() - 1))
+                }
+                Marker::Value(..) | Marker::Unknown => current,
+            };
+            continue;
+        }
+        if trim(line).is_empty() {
+            continue;
+        }
+        let active = !is_commented(line, comment);
+        let entry = match current {
+            None => continue,
+            Some((EntryKind::Option | EntryKind::Case, index)) => &mut entries[index],
+            Some((EntryKind::Section, start)) => {
+                let Some((_, name)) = section_name(line, comment, end_comment) else {
+                    continue;
+                };
+                let name = String::from_utf8_lossy(name).into_owned();
+                match entries[start..].iter().position(|entry| entry.name == name) {
+                    Some(pos) => &mut entries[start + pos],
+                    None => {
+                        entries.push(Entry {
+                            kind: EntryKind::Section,
+                            name,
+                            lines: (0, 0),
+                            state: State::Active,
+                        });
+                        // UNWRAP: we just pushed an entry.
+                        entries.last_mut().unwrap()
+                    }
+                }
+            }
+        };
+        if entry.lines.0 == 0 {
+            entry.lines.0 = number;
+            entry.state = State::merge(None, active);
+        } else {
+            entry.state = State::merge(Some(entry.state), active);
+        }
+        entry.lines.1 = number;
     }
+    // Options without any lines don't control anything.
+    entries.retain(|entry| entry.lines.0 != 0);
+    Ok(entries)
 }
 
 #[derive(Debug, Clone)]
@@ -292,10 +850,13 @@ struct Lines<'a> {
     bytes: &'a [u8],
     current_pos: usize,
 }
-impl<'a> Iterator for Lines<'a> {
-    type Item = &'a [u8];
-
-    fn next(&mut self) -> Option<Self::Item> {
+impl<'a> Lines<'a> {
+    /// Iterates over the lines together with their line endings.
+    fn with_endings(mut self) -> impl Iterator<Item = (&'a [u8], &'a [u8])> {
+        std::iter::from_fn(move || self.next_with_ending())
+    }
+    /// The next line and its line ending, which is empty at the end of the bytes.
+    fn next_with_ending(&mut self) -> Option<(&'a [u8], &'a [u8])> {
         if self.current_pos == self.bytes.len() {
             return None;
         }
@@ -311,18 +872,23 @@ impl<'a> Iterator for Lines<'a> {
             None => self.bytes.len(),
         };
         let bytes = &self.bytes[self.current_pos..end];
-        let new_start = {
-            if self.bytes[end] == 13 {
-                end + 2
-            } else {
-                end + 1
-            }
+        let new_start = match self.bytes.get(end) {
+            None => end,
+            Some(13) if self.bytes.get(end + 1) == Some(&10) => end + 2,
+            Some(_) => end + 1,
         };
         self.current_pos = new_start;
-        Some(bytes)
+        Some((bytes, &self.bytes[end..new_start]))
     }
 }
-fn get_lines(bytes: &[u8]) -> Lines {
+impl<'a> Iterator for Lines<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with_ending().map(|(line, _)| line)
+    }
+}
+fn get_lines(bytes: &[u8]) -> Lines<'_> {
     Lines {
         bytes,
         current_pos: 0,
@@ -367,13 +933,62 @@ fn get_last<'a>(bytes: &'a [u8], to_match: &[u8]) -> Option<&'a [u8]> {
     None
 }
 
-fn get_line_ending(bytes: &[u8]) -> &'static [u8] {
-    for byte in bytes.iter().copied() {
-        match byte {
-            13 => return b"\r\n",
-            10 => return b"\n",
-            _ => {}
-        }
+/// Finds the comment naming a line of a section, which is the last one of the line.
+/// Returns its position and the name.
+fn section_name<'a>(
+    line: &'a [u8],
+    comment: &[u8],
+    end_comment: Option<&[u8]>,
+) -> Option<(usize, &'a [u8])> {
+    let name = get_last(line, comment)?;
+    let pos = line.len() - name.len() - comment.len() - 1;
+    let name = match end_comment {
+        Some(end_comment) => trim(name).strip_suffix(end_comment)?,
+        None => name,
+    };
+    Some((pos, trim(name)))
+}
+
+/// Writes `content` with a `\` before the last byte of every `end_comment` in it,
+/// so the content can be put in a comment. Reversed by [`unescape_comment`].
+fn escape_comment(content: &[u8], end_comment: &[u8], output: &mut Vec<u8>) {
+    let Some((last, start)) = end_comment.split_last() else {
+        output.extend_from_slice(content);
+        return;
+    };
+    let mut rest = content;
+    while let Some(pos) = find(rest, end_comment) {
+        output.extend_from_slice(&rest[..pos]);
+        output.extend_from_slice(start);
+        output.push(b'\\');
+        output.push(*last);
+        rest = &rest[pos + end_comment.len()..];
+    }
+    output.extend_from_slice(rest);
+}
+fn unescape_comment(content: &[u8], end_comment: &[u8], output: &mut Vec<u8>) {
+    let Some((last, start)) = end_comment.split_last() else {
+        output.extend_from_slice(content);
+        return;
+    };
+    let mut escaped = start.to_vec();
+    escaped.push(b'\\');
+    escaped.push(*last);
+    let mut rest = content;
+    while let Some(pos) = find(rest, &escaped) {
+        output.extend_from_slice(&rest[..pos]);
+        output.extend_from_slice(end_comment);
+        rest = &rest[pos + escaped.len()..];
+    }
+    output.extend_from_slice(rest);
+}
+
+/// The position of the first `needle` in `haystack`.
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if needle.is_empty() {
+        return Some(0);
     }
-    b"\n"
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
 }
@@ -0,0 +1,278 @@
+//! Checks of the markers of config files, for mistakes which otherwise do nothing.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::profile::Manifest;
+use crate::{
+    detect_comment, expr, first_non_whitespace, get_lines, is_commented, parse_marker,
+    section_name, trim, value, Comment, Marker,
+};
+
+/// A mistake at a position in a config file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    /// 1-based.
+    pub line: usize,
+    /// The 1-based byte offset in the line.
+    pub column: usize,
+    /// A short, stable name of the kind of mistake, such as `unterminated`.
+    pub code: &'static str,
+    pub message: String,
+}
+impl Lint {
+    fn new(line: usize, column: usize, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            column,
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// The names used by the profiles of a manifest.
+#[derive(Debug, Clone, Default)]
+pub struct Known<'a> {
+    /// Names enabled or disabled by any profile.
+    pub names: HashSet<&'a [u8]>,
+    /// Names of values and selects set by any profile.
+    pub values: HashSet<&'a [u8]>,
+}
+impl<'a> Known<'a> {
+    pub fn from_manifest(manifest: &'a Manifest) -> Self {
+        let files = manifest.values().flat_map(|profile| &profile.files);
+        let mut known = Self::default();
+        for file in files {
+            known.names.extend(
+                file.enable
+                    .iter()
+                    .chain(&file.disable)
+                    .map(|name| name.trim().as_bytes()),
+            );
+            known
+                .values
+                .extend(file.set.keys().map(|name| name.as_bytes()));
+        }
+        known
+    }
+}
+
+/// A block started by a `CORPL` line and ended by `CORPL end`.
+struct Block {
+    kind: &'static str,
+    line: usize,
+    /// The number of non-empty lines in the block.
+    lines: usize,
+}
+
+fn end_block(block: Block, lints: &mut Vec<Lint>) {
+    if block.kind == "section" && block.lines == 0 {
+        lints.push(Lint::new(
+            block.line,
+            1,
+            "empty-section",
+            "The section has no lines.",
+        ));
+    }
+}
+
+/// Lints the config file at `path`.
+pub fn lint_file(
+    path: &Path,
+    comment: Option<Comment>,
+    max_comment_len: Option<usize>,
+    known: Option<&Known>,
+) -> Result<Vec<Lint>, Cow<'static, str>> {
+    let config = match fs::read(path) {
+        Ok(c) => c,
+        Err(_) => {
+            return Err(Cow::Borrowed(
+                "Failed to read config file. Check input path.",
+            ))
+        }
+    };
+    lint_config(&config, path, comment, max_comment_len, known)
+}
+
+/// Lints `config`, sorted by position. Names are only checked against `known` if it's set.
+/// `path` is used to detect the comment string and in error messages.
+pub fn lint_config(
+    config: &[u8],
+    path: &Path,
+    comment: Option<Comment>,
+    max_comment_len: Option<usize>,
+    known: Option<&Known>,
+) -> Result<Vec<Lint>, Cow<'static, str>> {
+    let comment = detect_comment(config, path, comment, max_comment_len)?;
+    let end_comment = comment.close;
+    let comment = comment.open;
+
+    let mut lints = Vec::new();
+    let check_name = |name: &[u8], values: bool| {
+        let known = known?;
+        let (set, message) = if values {
+            (&known.values, "isn't set by any profile")
+        } else {
+            (&known.names, "isn't enabled or disabled by any profile")
+        };
+        if set.contains(name) {
+            None
+        } else {
+            Some(format!("`{}` {}.", String::from_utf8_lossy(name), message))
+        }
+    };
+
+    let mut open: Option<Block> = None;
+    let mut section_common: &[u8] = b"";
+    for (index, line) in get_lines(config).enumerate() {
+        let number = index + 1;
+        let column = |slice: &[u8]| slice.as_ptr() as usize - line.as_ptr() as usize + 1;
+        let start = first_non_whitespace(line);
+
+        let Some(marker) = parse_marker(line, comment, end_comment) else {
+            if trim(line).is_empty() {
+                continue;
+            }
+            let Some(block) = &mut open else {
+                continue;
+            };
+            block.lines += 1;
+            if block.kind != "section" {
+                continue;
+            }
+            match section_name(line, comment, end_comment) {
+                None => lints.push(Lint::new(
+                    number,
+                    start + 1,
+                    "missing-name",
+                    "The line of the section has no name, so it's always commented out.",
+                )),
+                Some((_, name)) => {
+                    if let Some(message) = check_name(name, false) {
+                        lints.push(Lint::new(number, column(name), "unknown-name", message));
+                    }
+                }
+            }
+            if !is_commented(line, comment) && !line[start..].starts_with(section_common) {
+                lints.push(Lint::new(
+                    number,
+                    start + 1,
+                    "missing-prefix",
+                    format!(
+                        "The line doesn't start with the common string `{}` of the section on line {}.",
+                        String::from_utf8_lossy(section_common),
+                        block.line
+                    ),
+                ));
+            }
+            continue;
+        };
+
+        let kind = match marker {
+            Marker::Section(_) => Some("section"),
+            Marker::Option(..) => Some("option"),
+            Marker::Select(_) => Some("select"),
+            _ => None,
+        };
+        if let Some(kind) = kind {
+            if let Some(block) = open.take() {
+                lints.push(Lint::new(
+                    number,
+                    start + 1,
+                    "nested",
+                    format!(
+                        "The `{}` starts inside the `{}` on line {}, which has no `end`.",
+                        kind, block.kind, block.line
+                    ),
+                ));
+                end_block(block, &mut lints);
+            }
+            open = Some(Block {
+                kind,
+                line: number,
+                lines: 0,
+            });
+        }
+
+        match marker {
+            Marker::End => match open.take() {
+                Some(block) => end_block(block, &mut lints),
+                None => lints.push(Lint::new(
+                    number,
+                    start + 1,
+                    "stray-end",
+                    "The `end` has no block to end.",
+                )),
+            },
+            Marker::Section(common) => {
+                section_common = common;
+                if common.is_empty() {
+                    lints.push(Lint::new(
+                        number,
+                        start + 1,
+                        "empty-section",
+                        "The section has an empty common string.",
+                    ));
+                }
+            }
+            Marker::Option(option, offset) => match expr::Expr::parse(option) {
+                Ok(expr) => {
+                    for name in expr.names() {
+                        if let Some(message) = check_name(name, false) {
+                            lints.push(Lint::new(number, column(name), "unknown-name", message));
+                        }
+                    }
+                }
+                Err(err) => lints.push(Lint::new(
+                    number,
+                    offset + err.offset + 1,
+                    "syntax",
+                    format!("Invalid expression: {}.", err),
+                )),
+            },
+            Marker::Select(name) => {
+                if let Some(message) = check_name(name, true) {
+                    lints.push(Lint::new(number, column(name), "unknown-name", message));
+                }
+            }
+            Marker::Case(_) => {
+                if open.as_ref().is_none_or(|block| block.kind != "select") {
+                    lints.push(Lint::new(
+                        number,
+                        start + 1,
+                        "stray-case",
+                        "The `case` is outside of a `select`.",
+                    ));
+                }
+            }
+            Marker::Value(name, target, offset) => {
+                if let Err(err) = value::Target::parse(target) {
+                    lints.push(Lint::new(number, offset + 1, "syntax", format!("{}.", err)));
+                }
+                if let Some(message) = check_name(name, true) {
+                    lints.push(Lint::new(number, column(name), "unknown-name", message));
+                }
+            }
+            Marker::Unknown => lints.push(Lint::new(
+                number,
+                start + 1,
+                "unknown-marker",
+                "Unknown `CORPL` line.",
+            )),
+        }
+    }
+    if let Some(block) = open {
+        lints.push(Lint::new(
+            block.line,
+            1,
+            "unterminated",
+            format!("The `{}` has no `end`.", block.kind),
+        ));
+        end_block(block, &mut lints);
+    }
+    lints.sort_by_key(|lint| (lint.line, lint.column));
+    Ok(lints)
+}
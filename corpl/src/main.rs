@@ -1,10 +1,13 @@
-use std::collections::HashSet;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fmt::Display;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
 
-use clap::{self, Arg, ArgAction, Command};
+use clap::{self, Arg, ArgAction, ArgMatches, Command};
 
 use common::ExitDisplay;
-use corpl::Comment;
+use corpl::{lint, profile, Comment, EntryKind, Names, State, WriteMode};
 
 fn main() {
     let app = Command::new("corpl")
@@ -14,7 +17,81 @@ fn main() {
         .long_about(
             "Changes exposed values in config files.\n\
             Tries to find the appropriate comment string (e.g. '#' and '//') in the first line. \
-            A good practise for the first line to only contain the comment string.",
+            A good practise for the first line to only contain the comment string.\n\
+            A `corpl: comment=OPEN [close=CLOSE]` modeline in the first or last 5 lines, \
+            a shebang or the file extension can also set it. `-c` overrides them all.\n\
+            Options are expressions of names with `!`, `&&`, `||` and parentheses, \
+            such as `# CORPL option (dark || amoled) && !laptop`.",
+        )
+        .args_conflicts_with_subcommands(true)
+        .subcommand_negates_reqs(true)
+        .subcommand(
+            Command::new("list")
+                .about("Lists the options and sections of config files and whether they're active")
+                .arg(
+                    Arg::new("CONFIG")
+                        .help("The config files to list")
+                        .required(true)
+                        .num_args(1..),
+                ),
+        )
+        .subcommand(
+            Command::new("status")
+                .about(
+                    "Exits with status 0 if the lines of an option are active, \
+                    and 1 if they're commented out or mixed",
+                )
+                .arg(
+                    Arg::new("CONFIG")
+                        .help("The config file to check")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("option")
+                        .help(
+                            "The option to check. Matches the expression of an option, \
+                            the name of lines in a section, or SELECT=CASE",
+                        )
+                        .short('o')
+                        .long("option")
+                        .required(true)
+                        .num_args(1),
+                ),
+        )
+        .subcommand(
+            Command::new("lint")
+                .about("Checks the markers of config files for mistakes")
+                .arg(
+                    Arg::new("CONFIG")
+                        .help("The config files to check")
+                        .required(true)
+                        .num_args(1..),
+                )
+                .arg(manifest_arg().help(
+                    "The manifest of profiles, to check that the names are used. \
+                    Defaults to `$XDG_CONFIG_HOME/corpl/profiles.toml`, if it exists",
+                ))
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .action(ArgAction::SetTrue)
+                        .help("Print a JSON object per line, for editors"),
+                ),
+        )
+        .subcommand(write_args(
+            Command::new("apply")
+                .about("Processes all the files of a profile in the manifest")
+                .arg(
+                    Arg::new("PROFILE")
+                        .help("The name of the profile")
+                        .required(true),
+                )
+                .arg(manifest_arg()),
+        ))
+        .subcommand(
+            Command::new("profiles")
+                .about("Lists the profiles in the manifest")
+                .arg(manifest_arg()),
         )
         .arg(
             Arg::new("CONFIG")
@@ -22,8 +99,8 @@ fn main() {
                     "Sets the config files to change. \
                 It is recommended to only use one config \
                 file per instance of this program, \
-                since the `-c` option overrides all \
-                unrecognised comment strings.",
+                since the `-c` option overrides the \
+                comment strings of all of them.",
                 )
                 .required(true)
                 .num_args(1..),
@@ -44,30 +121,45 @@ fn main() {
                 )
                 .short('l')
                 .action(ArgAction::SetTrue)
-                .long("long-comment"),
+                .long("long-comment")
+                .global(true),
         )
         .arg(
             Arg::new("comment")
                 .help(
-                    "Override comment string found in file. \
+                    "Override comment string found in file, even by a modeline. \
                     Can be used if the program failed to register it.",
                 )
                 .short('c')
                 .long("comment")
-                .num_args(1),
+                .num_args(1)
+                .global(true),
         )
         .arg(
             Arg::new("closing-comment")
                 .help("An optional closing comment, for comments of type /* */")
                 .long("closing-comment")
-                .num_args(1),
+                .num_args(1)
+                .global(true),
         )
         .arg(
             Arg::new("keep")
                 .short('k')
                 .long("keep")
                 .action(ArgAction::SetTrue)
-                .help("Keep current settings"),
+                .help("Keep current settings. Names neither enabled nor disabled are ignored in options"),
+        )
+        .arg(
+            Arg::new("set")
+                .short('s')
+                .long("set")
+                .value_name("NAME=VALUE")
+                .help(
+                    "Sets the target of `CORPL value NAME` lines, \
+                    or the case of `CORPL select NAME` blocks. Can be multiple",
+                )
+                .action(ArgAction::Append)
+                .num_args(1),
         )
         .arg(
             Arg::new("disable")
@@ -77,9 +169,32 @@ fn main() {
                 .action(ArgAction::Append)
                 .num_args(1),
         );
+    let app = write_args(app);
 
     let matches = app.get_matches();
 
+    let comment = {
+        let primary = matches.get_one::<String>("comment").map(|s| s.as_bytes());
+        let closing = matches
+            .get_one::<String>("closing-comment")
+            .map(|s| s.as_bytes());
+        Comment::maybe_whole(primary, closing)
+    };
+    let comment_len = if matches.get_flag("long-comment") {
+        None
+    } else {
+        Some(4)
+    };
+
+    match matches.subcommand() {
+        Some(("list", matches)) => list(matches, comment, comment_len),
+        Some(("status", matches)) => status(matches, comment, comment_len),
+        Some(("lint", matches)) => lint(matches, comment, comment_len),
+        Some(("apply", matches)) => apply(matches, comment, comment_len),
+        Some(("profiles", matches)) => profiles(matches),
+        _ => {}
+    }
+
     let enable: HashSet<_> = match matches.get_many::<String>("enable") {
         Some(enable) => enable
             .flat_map(|s| s.split(','))
@@ -95,33 +210,117 @@ fn main() {
         None => HashSet::new(),
     };
     let keep = matches.get_flag("keep") || !disable.is_empty();
-
-    let comment = {
-        let primary = matches.get_one::<String>("comment").map(|s| s.as_bytes());
-        let closing = matches
-            .get_one::<String>("closing-comment")
-            .map(|s| s.as_bytes());
-        Comment::maybe_whole(primary, closing)
+    let values: HashMap<_, _> = match matches.get_many::<String>("set") {
+        Some(values) => values
+            .map(|s| match s.split_once('=') {
+                Some((name, value)) => (name.trim().as_bytes(), value.as_bytes()),
+                None => format!("`--set {s}` is missing a value. Use NAME=VALUE.").print_exit(),
+            })
+            .collect(),
+        None => HashMap::new(),
     };
-    let comment_len = if matches.get_flag("long-comment") {
-        None
-    } else {
-        Some(4)
+    let names = Names {
+        enabled: enable,
+        disabled: disable,
+        keep,
+        values,
     };
 
-    let mut errors = vec![];
-    for file in matches.get_many::<String>("CONFIG").unwrap() {
-        if let Err(err) = corpl::process_file(
-            Path::new(file),
+    let jobs = matches
+        .get_many::<String>("CONFIG")
+        .unwrap()
+        .map(|file| Job {
+            path: Path::new(file),
             comment,
-            &enable,
-            &disable,
-            keep,
+            names: names.clone(),
             comment_len,
-        ) {
-            errors.push((err, file))
+        })
+        .collect();
+    run(jobs, &matches)
+}
+
+/// Adds the arguments controlling how the files are written.
+fn write_args(command: Command) -> Command {
+    command
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .action(ArgAction::SetTrue)
+                .help("Print a diff of the changes instead of writing them"),
+        )
+        .arg(
+            Arg::new("check")
+                .long("check")
+                .action(ArgAction::SetTrue)
+                .help("Exit with status 1 if any file would change. Doesn't write the files"),
+        )
+        .arg(
+            Arg::new("backup")
+                .long("backup")
+                .value_name("SUFFIX")
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value(".bak")
+                .help(
+                    "Copy each changed file to its path with SUFFIX appended before writing. \
+                    Takes the form `--backup[=SUFFIX]`: the suffix must follow a `=`, \
+                    as in `--backup=.orig`. The default suffix is `.bak`",
+                ),
+        )
+}
+
+fn manifest_arg() -> Arg {
+    Arg::new("manifest")
+        .short('m')
+        .long("manifest")
+        .value_name("PATH")
+        .num_args(1)
+        .help("The manifest of profiles. Defaults to `$XDG_CONFIG_HOME/corpl/profiles.toml`")
+}
+
+/// A config file to process, with its settings.
+struct Job<'a> {
+    path: &'a Path,
+    comment: Option<Comment<'a>>,
+    names: Names<'a>,
+    comment_len: Option<usize>,
+}
+
+/// Processes `jobs`, and writes them according to the arguments of [`write_args`].
+fn run(jobs: Vec<Job>, matches: &ArgMatches) -> ! {
+    let dry_run = matches.get_flag("dry-run");
+    let check = matches.get_flag("check");
+    let mode = if dry_run || check {
+        WriteMode::DryRun
+    } else {
+        WriteMode::Replace {
+            backup_suffix: matches.get_one::<String>("backup").map(String::as_str),
+        }
+    };
+    let color = io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none();
+
+    let mut errors = vec![];
+    let mut changed = false;
+    for job in jobs {
+        let file = job.path.display();
+        match corpl::process_file(job.path, job.comment, &job.names, job.comment_len, mode) {
+            Ok(change) if change.is_changed() => {
+                changed = true;
+                if dry_run {
+                    print!("{}", corpl::diff::unified(job.path, &change, color));
+                } else if check {
+                    eprintln!("{file} would change.");
+                }
+            }
+            Ok(_) => {}
+            Err(err) => errors.push((err, file)),
         };
     }
+    exit_on_errors(errors);
+    std::process::exit(if check && changed { 1 } else { 0 })
+}
+
+fn exit_on_errors(mut errors: Vec<(impl Display, impl Display)>) {
     let last = errors.pop();
     for (err, path) in errors {
         eprintln!("{err} Error when processing {path}")
@@ -130,3 +329,178 @@ fn main() {
         format!("{err} Error when processing {path}").print_exit()
     }
 }
+
+fn list(matches: &ArgMatches, comment: Option<Comment>, comment_len: Option<usize>) -> ! {
+    let mut errors = vec![];
+    for file in matches.get_many::<String>("CONFIG").unwrap() {
+        let entries = match corpl::list_file(Path::new(file), comment, comment_len) {
+            Ok(entries) => entries,
+            Err(err) => {
+                errors.push((err, file));
+                continue;
+            }
+        };
+        for entry in entries {
+            let lines = match entry.lines {
+                (first, last) if first == last => first.to_string(),
+                (first, last) => format!("{first}-{last}"),
+            };
+            let kind = match entry.kind {
+                EntryKind::Section => "section",
+                EntryKind::Option => "option",
+                EntryKind::Case => "case",
+            };
+            println!(
+                "{file}:{lines}\t{kind}\t{}\t{}",
+                entry.name,
+                entry.state.name()
+            );
+        }
+    }
+    exit_on_errors(errors);
+    std::process::exit(0)
+}
+
+fn status(matches: &ArgMatches, comment: Option<Comment>, comment_len: Option<usize>) -> ! {
+    let file = matches.get_one::<String>("CONFIG").unwrap();
+    let option = matches.get_one::<String>("option").unwrap().trim();
+    let entries = match corpl::list_file(Path::new(file), comment, comment_len) {
+        Ok(entries) => entries,
+        Err(err) => format!("{err} Error when processing {file}").print_exit(),
+    };
+    let state = entries
+        .iter()
+        .filter(|entry| entry.name == option)
+        .map(|entry| entry.state)
+        .reduce(|a, b| if a == b { a } else { State::Mixed });
+    match state {
+        Some(state) => {
+            println!("{}", state.name());
+            std::process::exit(if state == State::Active { 0 } else { 1 })
+        }
+        None => {
+            eprintln!("No option or section line named '{option}' in {file}.");
+            std::process::exit(2)
+        }
+    }
+}
+
+fn read_manifest(matches: &ArgMatches) -> profile::Manifest {
+    let path = match matches.get_one::<String>("manifest") {
+        Some(path) => PathBuf::from(path),
+        None => match profile::default_path() {
+            Some(path) => path,
+            None => "Failed to find the config directory. Use `--manifest`.".print_exit(),
+        },
+    };
+    profile::read(&path).unwrap_or_else(|err| err.into_owned().print_exit())
+}
+
+fn apply(matches: &ArgMatches, comment: Option<Comment>, comment_len: Option<usize>) -> ! {
+    let manifest = read_manifest(matches);
+    let name = matches.get_one::<String>("PROFILE").unwrap();
+    let Some(profile) = manifest.get(name) else {
+        let available: Vec<&str> = manifest.keys().map(String::as_str).collect();
+        format!(
+            "No profile named '{name}'. Available are: {}.",
+            available.join(", ")
+        )
+        .print_exit()
+    };
+    let jobs = profile
+        .files
+        .iter()
+        .map(|file| Job {
+            path: &file.path,
+            comment: match &file.comment {
+                Some(open) => Comment::maybe_whole(
+                    Some(open.as_bytes()),
+                    file.closing_comment.as_deref().map(str::as_bytes),
+                ),
+                None => comment,
+            },
+            names: Names {
+                enabled: file.enable.iter().map(|s| s.trim().as_bytes()).collect(),
+                disabled: file.disable.iter().map(|s| s.trim().as_bytes()).collect(),
+                keep: file.keep || !file.disable.is_empty(),
+                values: file
+                    .set
+                    .iter()
+                    .map(|(name, value)| (name.as_bytes(), value.as_bytes()))
+                    .collect(),
+            },
+            comment_len: if file.long_comment { None } else { comment_len },
+        })
+        .collect();
+    run(jobs, matches)
+}
+
+fn profiles(matches: &ArgMatches) -> ! {
+    for (name, profile) in read_manifest(matches) {
+        match profile.description {
+            Some(description) => println!("{name}\t{description}"),
+            None => println!("{name}"),
+        }
+    }
+    std::process::exit(0)
+}
+
+fn lint(matches: &ArgMatches, comment: Option<Comment>, comment_len: Option<usize>) -> ! {
+    let manifest = match matches.get_one::<String>("manifest") {
+        Some(_) => Some(read_manifest(matches)),
+        None => profile::default_path()
+            .filter(|path| path.exists())
+            .map(|path| profile::read(&path).unwrap_or_else(|err| err.into_owned().print_exit())),
+    };
+    let known = manifest.as_ref().map(lint::Known::from_manifest);
+    let json = matches.get_flag("json");
+
+    let mut errors = vec![];
+    let mut found = false;
+    for file in matches.get_many::<String>("CONFIG").unwrap() {
+        let lints = match lint::lint_file(Path::new(file), comment, comment_len, known.as_ref()) {
+            Ok(lints) => lints,
+            Err(err) => {
+                errors.push((err, file));
+                continue;
+            }
+        };
+        found |= !lints.is_empty();
+        for lint in lints {
+            if json {
+                println!(
+                    "{{\"file\":{},\"line\":{},\"column\":{},\"code\":\"{}\",\"message\":{}}}",
+                    json_string(file),
+                    lint.line,
+                    lint.column,
+                    lint.code,
+                    json_string(&lint.message)
+                );
+            } else {
+                println!(
+                    "{file}:{}:{}: warning: {} [{}]",
+                    lint.line, lint.column, lint.message, lint.code
+                );
+            }
+        }
+    }
+    exit_on_errors(errors);
+    std::process::exit(if found { 1 } else { 0 })
+}
+
+/// Quotes `s` as a JSON string.
+fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,105 @@
+//! Manifests of profiles, which process many files at once.
+//!
+//! A manifest is a TOML file, by default `$XDG_CONFIG_HOME/corpl/profiles.toml`,
+//! with a table per profile:
+//!
+//! ```toml
+//! [dark]
+//! description = "Dark theme everywhere"
+//!
+//! [[dark.files]]
+//! path = "~/.config/alacritty/alacritty.toml"
+//! enable = ["dark"]
+//! disable = ["light"]
+//! set = { accent = "#89b4fa" }
+//!
+//! [[dark.files]]
+//! path = "~/.config/nvim/init.lua"
+//! comment = "--"
+//! enable = ["dark"]
+//! ```
+//!
+//! Relative paths are relative to the directory of the manifest.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+/// The profiles of a manifest, by name.
+pub type Manifest = BTreeMap<String, Profile>;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub files: Vec<ProfileFile>,
+}
+
+/// A config file of a profile, with the same settings as the command line.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ProfileFile {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub enable: Vec<String>,
+    /// Implies `keep`.
+    #[serde(default)]
+    pub disable: Vec<String>,
+    #[serde(default)]
+    pub keep: bool,
+    /// The values of `CORPL value` lines.
+    #[serde(default)]
+    pub set: BTreeMap<String, String>,
+    pub comment: Option<String>,
+    pub closing_comment: Option<String>,
+    #[serde(default)]
+    pub long_comment: bool,
+}
+
+/// The default path of the manifest, `$XDG_CONFIG_HOME/corpl/profiles.toml`,
+/// or `~/.config/corpl/profiles.toml` if the variable isn't set.
+pub fn default_path() -> Option<PathBuf> {
+    let config = match env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(config.join("corpl").join("profiles.toml"))
+}
+
+/// Reads the manifest at `path`, resolving the paths of the files.
+pub fn read(path: &Path) -> Result<Manifest, Cow<'static, str>> {
+    let manifest = fs::read_to_string(path).map_err(|_| {
+        Cow::Owned(format!(
+            "Failed to read the manifest {}. Check the path.",
+            path.display()
+        ))
+    })?;
+    let mut manifest: Manifest = toml::from_str(&manifest).map_err(|err| {
+        Cow::Owned(format!(
+            "Failed to parse the manifest {}: {}",
+            path.display(),
+            err.to_string().trim_end()
+        ))
+    })?;
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    for file in manifest.values_mut().flat_map(|profile| &mut profile.files) {
+        file.path = resolve(&file.path, dir);
+    }
+    Ok(manifest)
+}
+
+/// Expands a leading `~` to the home directory and makes `path` relative to `dir`.
+fn resolve(path: &Path, dir: &Path) -> PathBuf {
+    if let Ok(rest) = path.strip_prefix("~") {
+        if let Some(home) = env::var_os("HOME") {
+            return PathBuf::from(home).join(rest);
+        }
+    }
+    dir.join(path)
+}
@@ -0,0 +1,81 @@
+//! Targets of `CORPL value` lines, the part of the following line which is replaced.
+//!
+//! `# CORPL value NAME [TARGET]` sets the target on the next line to the value of `NAME`,
+//! where the target is one of:
+//!
+//! - `word` (the default): the last word, without a trailing `;` or `,`
+//! - `quoted`: the contents of the first string in `"` or `'`
+//! - `regex:PATTERN`: the first capture group of the first match, or the whole match
+//!   if there are no groups
+
+use std::ops::Range;
+
+use regex::bytes::Regex;
+
+#[derive(Debug, Clone)]
+pub enum Target {
+    Word,
+    Quoted,
+    Regex(Regex),
+}
+impl Target {
+    /// Parses the target of a `CORPL value` line. An empty string is [`Target::Word`].
+    pub fn parse(target: &[u8]) -> Result<Self, String> {
+        match target {
+            b"" | b"word" => Ok(Self::Word),
+            b"quoted" => Ok(Self::Quoted),
+            _ => {
+                let Some(pattern) = target.strip_prefix(b"regex:".as_ref()) else {
+                    return Err(format!(
+                        "Value target '{}' is not valid. Available are: \
+                        word, quoted and regex:PATTERN",
+                        String::from_utf8_lossy(target)
+                    ));
+                };
+                let pattern = std::str::from_utf8(pattern)
+                    .map_err(|_| "The pattern isn't valid UTF-8".to_owned())?;
+                Regex::new(pattern).map(Self::Regex).map_err(|err| {
+                    // Only keep the last line of the multi-line syntax errors.
+                    let err = err.to_string();
+                    let reason = err.trim_end().lines().last().unwrap_or_default();
+                    let reason = reason.strip_prefix("error: ").unwrap_or(reason);
+                    format!("Invalid pattern: {}", reason)
+                })
+            }
+        }
+    }
+
+    /// Finds the target in `line`.
+    pub fn find(&self, line: &[u8]) -> Option<Range<usize>> {
+        match self {
+            Self::Word => {
+                let end = line.iter().rposition(|b| !b.is_ascii_whitespace())? + 1;
+                let start = line[..end]
+                    .iter()
+                    .rposition(|b| b.is_ascii_whitespace())
+                    .map_or(0, |pos| pos + 1);
+                let word = &line[start..end];
+                let len = word
+                    .iter()
+                    .rposition(|b| !matches!(b, b';' | b','))
+                    .map_or(word.len(), |pos| pos + 1);
+                Some(start..start + len)
+            }
+            Self::Quoted => {
+                let open = line.iter().position(|b| matches!(b, b'"' | b'\''))?;
+                let close = line[open + 1..].iter().position(|b| *b == line[open])?;
+                Some(open + 1..open + 1 + close)
+            }
+            Self::Regex(regex) => {
+                let captures = regex.captures(line)?;
+                captures
+                    .iter()
+                    .skip(1)
+                    .flatten()
+                    .next()
+                    .or_else(|| captures.get(0))
+                    .map(|target| target.range())
+            }
+        }
+    }
+}